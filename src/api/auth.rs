@@ -1,33 +1,193 @@
-use axum::{ extract::State, http::StatusCode, response::IntoResponse, routing::post, Router };
+use axum::{
+    extract::State,
+    http::{ header::RETRY_AFTER, StatusCode },
+    response::{ IntoResponse, Response },
+    routing::post,
+    Router,
+};
 use serde::{ Deserialize, Serialize };
-use serde_json::json;
+use chrono::NaiveDateTime;
+use std::net::IpAddr;
+use utoipa::{ OpenApi, ToSchema };
 use uuid::Uuid;
 
+use super::users::UserProfile;
+
 use crate::application::{
-    api_error::ApiError,
+    api_client_ip::ClientIp,
+    api_error::{ ApiError, ApiErrorType },
     api_json::Json,
+    api_problem::ProblemDetails,
+    audit_service::{ self, AuditEvent },
+    email_verification_service,
+    lockout_service,
+    metrics_service,
+    password_reset_service,
+    registration_service::{ self, Registered, Registration },
     redis_service,
     repository::user_repo,
     security::{
         auth_error::AuthError,
         jwt_auth::{ self, JwtTokens },
         jwt_claims::{ AccessClaims, ClaimsMethods, RefreshClaims },
-        password::verify_password,
+        password::{ self, dummy_hash, verify_password },
     },
     state::SharedState,
 };
+use crate::infrastructure::telemetry;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 struct LoginUser {
     username: String,
     password: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+// the password is never printed
+impl std::fmt::Debug for LoginUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoginUser")
+            .field("username", &self.username)
+            .field("password", &telemetry::REDACTED)
+            .finish()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct RevokeUser {
     user_id: Uuid,
 }
 
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct UnlockLogin {
+    username: Option<String>,
+    #[schema(value_type = Option<String>, example = "192.0.2.1")]
+    ip: Option<IpAddr>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct ForgotPassword {
+    email: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct ResetPassword {
+    token: String,
+    new_password: String,
+}
+
+// the token and the password are never printed
+impl std::fmt::Debug for ResetPassword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResetPassword")
+            .field("token", &telemetry::REDACTED)
+            .field("new_password", &telemetry::REDACTED)
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct VerifyEmail {
+    token: String,
+}
+
+// the token is never printed
+impl std::fmt::Debug for VerifyEmail {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VerifyEmail").field("token", &telemetry::REDACTED).finish()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct ResendVerification {
+    email: String,
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct RegisterUser {
+    username: String,
+    email: String,
+    password: String,
+    /// required by the invite_only registration policy
+    invite_code: Option<String>,
+    /// required when a CAPTCHA is configured
+    captcha_response: Option<String>,
+}
+
+// the password and the invite code are never printed
+impl std::fmt::Debug for RegisterUser {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RegisterUser")
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .field("password", &telemetry::REDACTED)
+            .field("invite_code", &self.invite_code.as_ref().map(|_| telemetry::REDACTED))
+            .finish()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct RegisterResponse {
+    user: UserProfile,
+    /// with `REGISTRATION_AUTO_LOGIN`, unless the email has to be verified first or is taken
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tokens: Option<TokensResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct CreateInvite {
+    /// the invite is only valid for this email when set
+    email: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct InviteResponse {
+    invite_code: String,
+    expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct TokensResponse {
+    access_token: String,
+    refresh_token: String,
+    token_type: String,
+}
+
+impl From<JwtTokens> for TokensResponse {
+    fn from(jwt_tokens: JwtTokens) -> Self {
+        TokensResponse {
+            access_token: jwt_tokens.access_token,
+            refresh_token: jwt_tokens.refresh_token,
+            token_type: "Bearer".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct CleanupResponse {
+    deleted_tokens: usize,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        login_handler,
+        logout_handler,
+        refresh_handler,
+        revoke_all_handler,
+        revoke_user_handler,
+        cleanup_handler,
+        unlock_handler,
+        forgot_password_handler,
+        reset_password_handler,
+        verify_email_handler,
+        resend_verification_handler,
+        register_handler,
+        create_invite_handler
+    ),
+    tags((name = "auth", description = "Authentication and token management"))
+)]
+pub struct AuthApi;
+
 pub fn routes() -> Router<SharedState> {
     Router::new()
         .route("/login", post(login_handler))
@@ -36,8 +196,28 @@ pub fn routes() -> Router<SharedState> {
         .route("/revoke-all", post(revoke_all_handler))
         .route("/revoke-user", post(revoke_user_handler))
         .route("/cleanup", post(cleanup_handler))
+        .route("/unlock", post(unlock_handler))
+        .route("/password/forgot", post(forgot_password_handler))
+        .route("/password/reset", post(reset_password_handler))
+        .route("/email/verify", post(verify_email_handler))
+        .route("/email/resend", post(resend_verification_handler))
+        .route("/register", post(register_handler))
+        .route("/invites", post(create_invite_handler))
 }
 
+#[utoipa::path(
+    post,
+    path = "/login",
+    tag = "auth",
+    request_body = LoginUser,
+    responses(
+        (status = 200, description = "Access and refresh tokens", body = TokensResponse),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 403, description = "Email not verified while the verification is required", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 429, description = "Too many failed login attempts of the username or from the ip", body = ProblemDetails, content_type = "application/problem+json",
+            headers(("Retry-After" = u64, description = "Seconds until the next attempt")))
+    )
+)]
 #[tracing::instrument(
     level = tracing::Level::TRACE,
     name = "login",
@@ -46,21 +226,67 @@ pub fn routes() -> Router<SharedState> {
 )]
 async fn login_handler(
     State(state): State<SharedState>,
+    ClientIp(ip): ClientIp,
     Json(login): Json<LoginUser>
-) -> Result<impl IntoResponse, ApiError> {
-    if let Some(user) = user_repo::get_user_by_username(&login.username, &state).await {
-        if user.active && verify_password(&user.password, login.password.as_bytes()).is_ok() {
+) -> Result<impl IntoResponse, Response> {
+    // checked before the user is looked up, the unknown usernames are blocked the same way
+    if let Some(retry_after) = lockout_service::blocked(&state, &login.username, ip).await {
+        audit_service::record(AuditEvent::LoginBlocked {
+            username: &login.username,
+            ip,
+            retry_after_seconds: retry_after,
+        });
+        metrics_service::record_login(false);
+        let error = ApiError::from(AuthError::TooManyAttempts);
+        return Err(([(RETRY_AFTER, retry_after)], error).into_response());
+    }
+
+    // the password is verified whether the user exists and is active or not,
+    // so the response time does not tell the usernames apart
+    let config = state.config();
+    let user = user_repo::get_user_by_username(&login.username, &state).await;
+    let password_hash = user.as_ref().map_or_else(|| dummy_hash(&config), |user| user.password.clone());
+    let verified = verify_password(&password_hash, login.password.as_bytes(), &config).is_ok();
+    if let Some(user) = user {
+        if user.active && verified {
+            if config.email_verification_required && user.email_verified_at.is_none() {
+                tracing::error!("access denied, email not verified, user: {}", user.id);
+                metrics_service::record_login(false);
+                return Err(ApiError::from(AuthError::EmailNotVerified).into_response());
+            }
             tracing::trace!("access granted, user: {}", user.id);
-            let tokens = jwt_auth::generate_tokens(user);
+            if password::needs_rehash(&user.password, &config) {
+                // the password is only known at login, the hash is upgraded to the current parameters
+                let new_hash = password::hash_password(login.password.as_bytes(), &config);
+                user_repo::update_password(user.id, &new_hash, &state).await;
+            }
+            audit_service::record(AuditEvent::LoginSucceeded { username: &login.username, ip });
+            metrics_service::record_login(true);
+            lockout_service::record_success(&state, &login.username).await;
+            let tokens = jwt_auth::generate_tokens(user, &config);
             let response = tokens_to_response(tokens);
             return Ok(response);
         }
     }
 
-    tracing::error!("access denied: {:#?}", login);
-    Err(AuthError::WrongCredentials.into())
+    tracing::error!("access denied, username: {}", login.username);
+    audit_service::record(AuditEvent::LoginFailed { username: &login.username, ip });
+    metrics_service::record_login(false);
+    lockout_service::record_failure(&state, &login.username, ip).await;
+    Err(ApiError::from(AuthError::WrongCredentials).into_response())
 }
 
+#[utoipa::path(
+    post,
+    path = "/logout",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Refresh token and its paired access token revoked"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 406, description = "Revoked tokens are disabled", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn logout_handler(
     State(state): State<SharedState>,
     refresh_claims: RefreshClaims
@@ -69,6 +295,16 @@ async fn logout_handler(
     jwt_auth::logout(refresh_claims, state).await
 }
 
+#[utoipa::path(
+    post,
+    path = "/refresh",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "New access and refresh tokens", body = TokensResponse),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn refresh_handler(
     State(state): State<SharedState>,
     refresh_claims: RefreshClaims
@@ -78,6 +314,16 @@ async fn refresh_handler(
 }
 
 // revoke all issued tokens until now
+#[utoipa::path(
+    post,
+    path = "/revoke-all",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "All issued tokens revoked"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn revoke_all_handler(
     State(state): State<SharedState>,
     access_claims: AccessClaims
@@ -90,6 +336,17 @@ async fn revoke_all_handler(
 }
 
 // revoke tokens issued to user until now
+#[utoipa::path(
+    post,
+    path = "/revoke-user",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    request_body = RevokeUser,
+    responses(
+        (status = 200, description = "Tokens of the user revoked"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn revoke_user_handler(
     State(state): State<SharedState>,
     access_claims: AccessClaims,
@@ -106,27 +363,211 @@ async fn revoke_user_handler(
     Ok(())
 }
 
+#[utoipa::path(
+    post,
+    path = "/cleanup",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Expired tokens deleted from the revoked list", body = CleanupResponse),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 406, description = "Revoked tokens are disabled", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn cleanup_handler(
     State(state): State<SharedState>,
     access_claims: AccessClaims
 ) -> Result<impl IntoResponse, ApiError> {
     access_claims.validate_role_admin()?;
     tracing::trace!("authentication details: {:#?}", access_claims);
-    let deleted = jwt_auth::cleanup_revoked_and_expired(&access_claims, &state).await?;
-    let json = json!({
-        "deleted_tokens": deleted,
-    });
-    Ok(Json(json))
-}
-
-fn tokens_to_response(jwt_tokens: JwtTokens) -> impl IntoResponse {
-    let json =
-        json!({
-        "access_token": jwt_tokens.access_token,
-        "refresh_token": jwt_tokens.refresh_token,
-        "token_type": "Bearer"
-    });
-
-    tracing::trace!("JWT: generated response {:#?}", json);
-    Json(json)
+    let deleted_tokens = jwt_auth::cleanup_revoked_and_expired(&access_claims, &state).await?;
+    Ok(Json(CleanupResponse { deleted_tokens }))
+}
+
+// unlock a username or an ip locked out after failed logins
+#[utoipa::path(
+    post,
+    path = "/unlock",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    request_body = UnlockLogin,
+    responses(
+        (status = 200, description = "Username or ip unlocked and its failed logins forgotten"),
+        (status = 400, description = "Neither a username nor an ip", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn unlock_handler(
+    State(state): State<SharedState>,
+    access_claims: AccessClaims,
+    Json(unlock): Json<UnlockLogin>
+) -> Result<impl IntoResponse, ApiError> {
+    access_claims.validate_role_admin()?;
+    if unlock.username.is_none() && unlock.ip.is_none() {
+        return Err(ApiError {
+            status_code: StatusCode::BAD_REQUEST,
+            error_type: ApiErrorType::Api,
+            error_message: "A username or an ip is required".to_string(),
+        });
+    }
+    let unlocked = lockout_service::unlock(
+        &state,
+        unlock.username.as_deref(),
+        unlock.ip,
+        &access_claims.sub
+    ).await;
+    if !unlocked {
+        return Err(ApiError::from(StatusCode::INTERNAL_SERVER_ERROR));
+    }
+    Ok(())
+}
+
+// send a password reset token to the email
+#[utoipa::path(
+    post,
+    path = "/password/forgot",
+    tag = "auth",
+    request_body = ForgotPassword,
+    responses(
+        (status = 202, description = "Reset token sent if the email belongs to an active user")
+    )
+)]
+async fn forgot_password_handler(
+    State(state): State<SharedState>,
+    Json(forgot): Json<ForgotPassword>
+) -> impl IntoResponse {
+    // sent in the background, the response does not wait for the lookup and the delivery
+    state.shutdown.spawn_task(
+        "password reset mail",
+        password_reset_service::request_reset(forgot.email, state.clone())
+    );
+    StatusCode::ACCEPTED
+}
+
+// set a new password with a reset token
+#[utoipa::path(
+    post,
+    path = "/password/reset",
+    tag = "auth",
+    request_body = ResetPassword,
+    responses(
+        (status = 200, description = "Password set and the sessions of the user revoked"),
+        (status = 422, description = "Invalid, expired or used token or new password rejected by the policy", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn reset_password_handler(
+    State(state): State<SharedState>,
+    Json(reset): Json<ResetPassword>
+) -> Result<impl IntoResponse, ApiError> {
+    tracing::trace!("reset: {:?}", reset);
+    password_reset_service::reset(&reset.token, &reset.new_password, &state).await
+}
+
+// verify the email with the token sent to it
+#[utoipa::path(
+    post,
+    path = "/email/verify",
+    tag = "auth",
+    request_body = VerifyEmail,
+    responses(
+        (status = 200, description = "Email verified"),
+        (status = 422, description = "Invalid, expired or used token, or the email changed since", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn verify_email_handler(
+    State(state): State<SharedState>,
+    Json(verify): Json<VerifyEmail>
+) -> Result<impl IntoResponse, ApiError> {
+    tracing::trace!("verify: {:?}", verify);
+    email_verification_service::verify(&verify.token, &state).await
+}
+
+// send a new verification token to an unverified email
+#[utoipa::path(
+    post,
+    path = "/email/resend",
+    tag = "auth",
+    request_body = ResendVerification,
+    responses(
+        (status = 202, description = "Verification token sent if the email of an active user is not verified")
+    )
+)]
+async fn resend_verification_handler(
+    State(state): State<SharedState>,
+    Json(resend): Json<ResendVerification>
+) -> impl IntoResponse {
+    // sent in the background, the response does not tell the registered emails apart
+    state.shutdown.spawn_task(
+        "email verification mail",
+        email_verification_service::resend(resend.email, state.clone())
+    );
+    StatusCode::ACCEPTED
+}
+
+// register a guest user following the registration policy
+#[utoipa::path(
+    post,
+    path = "/register",
+    tag = "auth",
+    request_body = RegisterUser,
+    responses(
+        (status = 201, description = "User registered, or the owner of the taken email notified, with tokens when logged in", body = RegisterResponse),
+        (status = 404, description = "Registration is disabled", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid fields or taken username, invite code, CAPTCHA or email domain, or password rejected by the policy", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn register_handler(
+    State(state): State<SharedState>,
+    ClientIp(ip): ClientIp,
+    Json(register): Json<RegisterUser>
+) -> Result<impl IntoResponse, ApiError> {
+    tracing::trace!("register: {:?}", register);
+    let registration = Registration {
+        username: &register.username,
+        email: &register.email,
+        password: &register.password,
+        invite_code: register.invite_code.as_deref(),
+        captcha_response: register.captcha_response.as_deref(),
+    };
+    let (user, created) = match registration_service::register(registration, ip, &state).await? {
+        Registered::Created(user) => (user, true),
+        Registered::EmailTaken(user) => (user, false),
+    };
+
+    let config = state.config();
+    let logged_in = created && config.registration_auto_login && !config.email_verification_required;
+    let tokens = logged_in.then(|| jwt_auth::generate_tokens(user.clone(), &config).into());
+    let response = RegisterResponse { user: user.into(), tokens };
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+// issue an invite code for the invite_only registration policy
+#[utoipa::path(
+    post,
+    path = "/invites",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    request_body = CreateInvite,
+    responses(
+        (status = 201, description = "Invite code issued, it is shown only once", body = InviteResponse),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid email", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn create_invite_handler(
+    State(state): State<SharedState>,
+    access_claims: AccessClaims,
+    Json(invite): Json<CreateInvite>
+) -> Result<impl IntoResponse, ApiError> {
+    access_claims.validate_role_admin()?;
+    let (invite_code, expires_at) = registration_service::create_invite(
+        invite.email.as_deref(),
+        &access_claims.sub,
+        &state
+    ).await?;
+    Ok((StatusCode::CREATED, Json(InviteResponse { invite_code, expires_at })))
+}
+
+pub(crate) fn tokens_to_response(jwt_tokens: JwtTokens) -> impl IntoResponse {
+    Json(TokensResponse::from(jwt_tokens))
 }
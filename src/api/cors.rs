@@ -0,0 +1,168 @@
+use axum::{ extract::{ Request, State }, http::{ HeaderName, HeaderValue, Method }, middleware::Next, response::Response };
+use std::{ sync::{ Arc, RwLock }, time::Duration };
+use tower::{ Layer, ServiceExt };
+use tower_http::cors::{ AllowHeaders, AllowOrigin, Any, CorsLayer, ExposeHeaders };
+
+use crate::application::{ config::Config, state::SharedState };
+
+/// An allowed origin: `https://app.example.com` or `https://*.example.com` for any subdomain
+#[derive(Debug, Clone, PartialEq)]
+pub enum OriginPattern {
+    Exact(String),
+    Subdomain {
+        prefix: String,
+        suffix: String,
+    },
+}
+
+impl OriginPattern {
+    pub fn new(origin: &str) -> Self {
+        match origin.split_once("://*.") {
+            Some((scheme, domain)) =>
+                Self::Subdomain {
+                    prefix: format!("{}://", scheme),
+                    suffix: format!(".{}", domain),
+                },
+            None => Self::Exact(origin.to_string()),
+        }
+    }
+
+    pub fn matches(&self, origin: &str) -> bool {
+        match self {
+            Self::Exact(exact) => exact.eq_ignore_ascii_case(origin),
+            Self::Subdomain { prefix, suffix } => {
+                let origin = origin.to_ascii_lowercase();
+                let Some(subdomain) = origin
+                    .strip_prefix(prefix.as_str())
+                    .and_then(|origin| origin.strip_suffix(suffix.as_str())) else {
+                    return false;
+                };
+                !subdomain.is_empty() &&
+                    subdomain.split('.').all(|label| {
+                        !label.is_empty() &&
+                            label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+                    })
+            }
+        }
+    }
+}
+
+/// The CORS policies built from the configuration:
+/// the public routes accept any origin without credentials, the other routes use the configured policy
+pub struct CorsPolicies {
+    default: CorsLayer,
+    public: CorsLayer,
+    public_routes: Vec<String>,
+}
+
+impl CorsPolicies {
+    pub fn new(config: &Config) -> Self {
+        let max_age = Duration::from_secs(config.cors_max_age_seconds);
+
+        let allow_origin = if config.cors_allowed_origins.iter().any(|origin| origin == "*") {
+            AllowOrigin::any()
+        } else {
+            let patterns: Arc<Vec<OriginPattern>> = Arc::new(
+                config.cors_allowed_origins
+                    .iter()
+                    .map(|origin| OriginPattern::new(origin))
+                    .collect()
+            );
+            AllowOrigin::predicate(move |origin: &HeaderValue, _| {
+                origin
+                    .to_str()
+                    .map(|origin| patterns.iter().any(|pattern| pattern.matches(origin)))
+                    .unwrap_or(false)
+            })
+        };
+
+        let methods: Vec<Method> = parse_all(&config.cors_allowed_methods);
+        let allow_headers = if config.cors_allowed_headers.iter().any(|header| header == "*") {
+            AllowHeaders::any()
+        } else {
+            AllowHeaders::list(parse_all::<HeaderName>(&config.cors_allowed_headers))
+        };
+        let expose_headers = if config.cors_exposed_headers.iter().any(|header| header == "*") {
+            ExposeHeaders::any()
+        } else {
+            ExposeHeaders::list(parse_all::<HeaderName>(&config.cors_exposed_headers))
+        };
+
+        let default = CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods(methods)
+            .allow_headers(allow_headers)
+            .expose_headers(expose_headers.clone())
+            .allow_credentials(config.cors_allow_credentials)
+            .max_age(max_age);
+
+        let public = CorsLayer::new()
+            .allow_origin(Any)
+            .allow_methods([Method::GET, Method::HEAD])
+            .allow_headers(Any)
+            .expose_headers(expose_headers)
+            .max_age(max_age);
+
+        Self {
+            default,
+            public,
+            public_routes: config.cors_public_routes.clone(),
+        }
+    }
+
+    pub fn layer(&self, path: &str) -> &CorsLayer {
+        let public = self.public_routes.iter().any(|route| {
+            path.strip_prefix(route.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        });
+        if public {
+            &self.public
+        } else {
+            &self.default
+        }
+    }
+}
+
+/// Applies the CORS policy of the current configuration,
+/// the policies are rebuilt when the configuration is reloaded
+pub struct Cors {
+    state: SharedState,
+    cache: RwLock<Option<(Arc<Config>, Arc<CorsPolicies>)>>,
+}
+
+impl Cors {
+    pub fn new(state: SharedState) -> Arc<Self> {
+        Arc::new(Self {
+            state,
+            cache: RwLock::new(None),
+        })
+    }
+
+    fn policies(&self) -> Arc<CorsPolicies> {
+        let config = self.state.config();
+        if let Some((cached, policies)) = self.cache.read().unwrap().as_ref() {
+            if Arc::ptr_eq(cached, &config) {
+                return policies.clone();
+            }
+        }
+        let policies = Arc::new(CorsPolicies::new(&config));
+        *self.cache.write().unwrap() = Some((config, policies.clone()));
+        policies
+    }
+}
+
+pub async fn cors_middleware(State(cors): State<Arc<Cors>>, request: Request, next: Next) -> Response {
+    let policies = cors.policies();
+    let layer = policies.layer(request.uri().path()).clone();
+    match layer.layer(next).oneshot(request).await {
+        Ok(response) => response,
+        Err(e) => match e {},
+    }
+}
+
+// the values are validated when the configuration is loaded
+fn parse_all<T: std::str::FromStr>(values: &[String]) -> Vec<T> {
+    values
+        .iter()
+        .filter_map(|value| value.parse().ok())
+        .collect()
+}
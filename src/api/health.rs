@@ -0,0 +1,48 @@
+use axum::{ extract::State, http::StatusCode, response::IntoResponse, routing::get, Router };
+use serde_json::json;
+use utoipa::OpenApi;
+
+use crate::application::{
+    api_json::Json,
+    health_service::{ self, Readiness },
+    state::SharedState,
+};
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(live_handler, ready_handler),
+    tags((name = "health", description = "Liveness and readiness probes"))
+)]
+pub struct HealthApi;
+
+pub fn routes() -> Router<SharedState> {
+    Router::new().route("/live", get(live_handler)).route("/ready", get(ready_handler))
+}
+
+#[utoipa::path(
+    get,
+    path = "/live",
+    tag = "health",
+    responses((status = 200, description = "The process is up"))
+)]
+async fn live_handler() -> impl IntoResponse {
+    Json(json!({ "status": "alive" }))
+}
+
+#[utoipa::path(
+    get,
+    path = "/ready",
+    tag = "health",
+    responses(
+        (status = 200, description = "All dependencies are reachable", body = Readiness),
+        (status = 503, description = "A dependency is not reachable or the service is shutting down", body = Readiness)
+    )
+)]
+async fn ready_handler(State(state): State<SharedState>) -> impl IntoResponse {
+    let readiness = health_service::readiness(&state).await;
+    if !readiness.ready && !readiness.shutting_down {
+        tracing::error!("service is not ready: {:?}", readiness);
+    }
+    let status_code = if readiness.ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status_code, Json(readiness))
+}
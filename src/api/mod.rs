@@ -1,3 +1,9 @@
 pub mod auth;
+pub mod cors;
+pub mod health;
+pub mod openapi;
+pub mod rate_limit;
+pub mod redirect;
 pub mod router;
 pub mod users;
+pub mod version;
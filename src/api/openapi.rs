@@ -0,0 +1,63 @@
+use axum::{ http::header, response::IntoResponse, routing::get, Router };
+use std::sync::OnceLock;
+use utoipa::{
+    openapi::security::{ HttpAuthScheme, HttpBuilder, SecurityScheme },
+    Modify,
+    OpenApi,
+};
+
+use super::{ auth::AuthApi, health::HealthApi, router, users::UsersApi };
+use crate::application::{ api_problem::ProblemDetails, state::SharedState };
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "axum-web"),
+    nest((path = "/v1", api = V1Api), (path = "/health", api = HealthApi)),
+    components(schemas(ProblemDetails)),
+    modifiers(&BearerSecurity),
+    tags((name = "service", description = "Service status"))
+)]
+pub struct ApiDoc;
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(router::heartbeat_handler),
+    nest((path = "/auth", api = AuthApi), (path = "/users", api = UsersApi))
+)]
+struct V1Api;
+
+// registers the JWT bearer authentication scheme referenced by the protected routes
+struct BearerSecurity;
+
+impl Modify for BearerSecurity {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new().scheme(HttpAuthScheme::Bearer).bearer_format("JWT").build()
+            )
+        );
+    }
+}
+
+pub fn routes() -> Router<SharedState> {
+    let router = Router::new().route("/openapi.json", get(openapi_handler));
+
+    // the Swagger UI assets are embedded in the binary, the page works without the network
+    #[cfg(feature = "swagger-ui")]
+    let router = router.merge(
+        utoipa_swagger_ui::SwaggerUi
+            ::new("/swagger-ui")
+            .config(utoipa_swagger_ui::Config::new(["/openapi.json"]))
+    );
+
+    router
+}
+
+// the specification is generated from the annotations once, on the first request
+async fn openapi_handler() -> impl IntoResponse {
+    static OPENAPI_JSON: OnceLock<String> = OnceLock::new();
+    let json = OPENAPI_JSON.get_or_init(|| ApiDoc::openapi().to_json().unwrap());
+    ([(header::CONTENT_TYPE, "application/json")], json.as_str())
+}
@@ -0,0 +1,119 @@
+use axum::{
+    extract::{ Request, State },
+    http::{ header::{ AUTHORIZATION, RETRY_AFTER }, HeaderMap, HeaderValue, StatusCode },
+    middleware::Next,
+    response::{ IntoResponse, Response },
+};
+use sha2::{ Digest, Sha256 };
+use std::time::Duration;
+
+use crate::application::{
+    api_client_ip::client_ip,
+    api_error::{ ApiError, ApiErrorType },
+    app_const::{ RATE_LIMIT_LIMIT, RATE_LIMIT_REMAINING, RATE_LIMIT_RESET },
+    config::{ Config, RateLimitKey, RateLimitRule },
+    metrics_service,
+    rate_limit_service::{ self, RateLimitDecision },
+    security::jwt_claims::{ self, AccessClaims },
+    state::SharedState,
+};
+
+const EXEMPT_PATHS: &[&str] = &["/health", "/metrics"];
+
+/// Limits the requests of each client by the rule of the longest path matching the request,
+/// the responses carry the `RateLimit-*` headers and the rejected ones a `Retry-After` header
+pub async fn rate_limit_middleware(State(state): State<SharedState>, request: Request, next: Next) -> Response {
+    let config = state.config();
+    if !config.rate_limit_enabled {
+        return next.run(request).await;
+    }
+    let path = request.uri().path();
+    if is_exempt(path) {
+        return next.run(request).await;
+    }
+    let Some(rule) = matching_rule(&config.rate_limit_rules, path) else {
+        return next.run(request).await;
+    };
+
+    // the clients without a known ip would share a single budget
+    let Some(client) = client_key(&request, rule.key, &config) else {
+        tracing::trace!("rate limit skipped, the client ip is unknown");
+        return next.run(request).await;
+    };
+    let decision = rate_limit_service::check(&state, config.rate_limit_store, rule, &client).await;
+
+    let mut response = if decision.allowed {
+        next.run(request).await
+    } else {
+        tracing::warn!("rate limit {:?} exceeded by {}", rule, client);
+        metrics_service::record_rate_limited(&rule.path);
+        let retry_after = seconds(decision.retry_after);
+        let mut response = ApiError {
+            status_code: StatusCode::TOO_MANY_REQUESTS,
+            error_type: ApiErrorType::Api,
+            error_message: format!("Too many requests, retry after {} seconds", retry_after),
+        }.into_response();
+        response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(retry_after));
+        response
+    };
+    insert_headers(response.headers_mut(), &decision);
+    response
+}
+
+// the probes and the metrics scrapes stay available to the orchestrator and the monitoring
+fn is_exempt(path: &str) -> bool {
+    EXEMPT_PATHS.iter().any(|exempt| {
+        path.strip_prefix(exempt).is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    })
+}
+
+fn matching_rule<'a>(rules: &'a [RateLimitRule], path: &str) -> Option<&'a RateLimitRule> {
+    rules
+        .iter()
+        .filter(|rule| {
+            path.strip_prefix(rule.path.as_str()).is_some_and(|rest| {
+                rest.is_empty() || rest.starts_with('/') || rule.path.ends_with('/')
+            })
+        })
+        .max_by_key(|rule| rule.path.len())
+}
+
+// the user and the api key fall back to the ip for the anonymous requests,
+// the api keys are hashed to keep them out of the store
+fn client_key(request: &Request, key: RateLimitKey, config: &Config) -> Option<String> {
+    match key {
+        RateLimitKey::User => {
+            if let Some(claims) = access_claims(request.headers(), config) {
+                return Some(format!("user:{}", claims.sub));
+            }
+        }
+        RateLimitKey::ApiKey => {
+            if let Some(api_key) = request.headers().get(config.rate_limit_api_key_header.as_str()) {
+                return Some(format!("api_key:{:x}", Sha256::digest(api_key.as_bytes())));
+            }
+        }
+        RateLimitKey::Ip => {}
+    }
+    client_ip(request.headers(), request.extensions(), config).map(|ip| format!("ip:{}", ip))
+}
+
+// the token is only decoded to identify the user, it is validated by the handlers
+fn access_claims(headers: &HeaderMap, config: &Config) -> Option<AccessClaims> {
+    let token = headers
+        .get(AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")?;
+    jwt_claims::decode_token(token, config).ok()
+}
+
+fn insert_headers(headers: &mut HeaderMap, decision: &RateLimitDecision) {
+    headers.insert(RATE_LIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATE_LIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATE_LIMIT_RESET, HeaderValue::from(seconds(decision.reset_after)));
+}
+
+// whole seconds rounded up, at least one
+fn seconds(duration: Duration) -> u64 {
+    duration.as_millis().div_ceil(1000).max(1) as u64
+}
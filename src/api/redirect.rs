@@ -0,0 +1,42 @@
+use axum::{
+    extract::Request,
+    http::{ header::{ HOST, LOCATION }, uri::Authority, StatusCode },
+    response::{ IntoResponse, Response },
+    Router,
+};
+
+use crate::application::api_error::{ ApiError, ApiErrorType };
+
+/// Redirects the plain HTTP requests to the HTTPS service,
+/// permanently and preserving the request method
+pub fn routes(https_port: u16) -> Router {
+    Router::new().fallback(move |request: Request| async move { redirect(request, https_port) })
+}
+
+fn redirect(request: Request, https_port: u16) -> Response {
+    let host = request
+        .headers()
+        .get(HOST)
+        .and_then(|host| host.to_str().ok())
+        .and_then(|host| host.parse::<Authority>().ok());
+    let Some(host) = host else {
+        return ApiError {
+            status_code: StatusCode::BAD_REQUEST,
+            error_type: ApiErrorType::Api,
+            error_message: "Missing or invalid host header".to_string(),
+        }.into_response();
+    };
+
+    let authority = if https_port == 443 {
+        host.host().to_string()
+    } else {
+        format!("{}:{}", host.host(), https_port)
+    };
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    let location = format!("https://{}{}", authority, path_and_query);
+    (StatusCode::PERMANENT_REDIRECT, [(LOCATION, location)]).into_response()
+}
@@ -1,50 +1,135 @@
 use axum::{
     body::Body,
-    extract::Request,
-    http::{ Method, StatusCode },
-    middleware::Next,
+    extract::{ MatchedPath, Request, State },
+    http::{ header::{ CONTENT_LENGTH, CONTENT_TYPE }, Method, StatusCode },
+    middleware::{ self, Next },
     response::{ IntoResponse, Response },
     routing::get,
     Router,
 };
-use std::collections::HashMap;
+use std::{ collections::HashMap, time::Instant };
 
-use super::{ auth, users };
+use super::{ auth, health, openapi, users, version::{ self, ApiVersion, API_VERSIONS } };
 
-use crate::application::{
-    api_error::{ ApiError, ApiErrorType },
-    api_json::Json,
-    api_path::Path,
-    app_const::*,
-    state::SharedState,
+use crate::{
+    application::{
+        api_error::{ ApiError, ApiErrorType },
+        api_json::Json,
+        api_path::Path,
+        api_problem::ProblemDetails,
+        app_const::*,
+        metrics_service,
+        state::SharedState,
+    },
+    infrastructure::telemetry,
 };
 
 pub fn routes(state: SharedState) -> Router {
-    // build the service routes
-    Router::new()
+    // nesting the routes of each served api version under its prefix: /v1, /v2, ...
+    let router = API_VERSIONS.iter().fold(Router::new(), |router, info| {
+        router.nest(&info.version.prefix(), version_routes(info.version))
+    });
+
+    router
+        // Prometheus metrics
+        .route("/metrics", get(metrics_handler))
+        // liveness and readiness probes
+        .nest("/health", health::routes())
+        // OpenAPI specification (and Swagger UI if enabled)
+        .merge(openapi::routes())
+        // add a fallback service for handling routes to unknown paths
+        .fallback(error_404_handler)
+        .with_state(state)
+}
+
+// the first path segments of the version routes,
+// only these unversioned paths are routed by the `Accept-Version` header
+pub const VERSION_ROUTES: &[&str] = &["head", "heartbeat", "auth", "users"];
+
+// builds the service routes of an api version,
+// a new version shares the handlers and overrides or adds only the routes that changed
+fn version_routes(version: ApiVersion) -> Router<SharedState> {
+    let router = Router::new()
         .route("/head", get(head_request_handler))
         .route("/heartbeat/:id", get(heartbeat_handler))
         // nesting the authentication related routes
         .nest("/auth", auth::routes())
         // nesting the user related routes
-        .nest("/users", users::routes())
-        // add a fallback service for handling routes to unknown paths
-        .fallback(error_404_handler)
-        .with_state(state)
+        .nest("/users", users::routes());
+
+    let router = match version {
+        ApiVersion::V1 => router,
+    };
+
+    router.layer(middleware::from_fn_with_state(version, version::version_headers_middleware))
 }
 
+// the root span of a request, continues the remote trace if the caller sent the trace context
 #[tracing::instrument(
-    level = tracing::Level::TRACE,
+    level = tracing::Level::INFO,
     name = "axum",
     skip_all,
-    fields(method = request.method().to_string(), uri = request.uri().to_string())
+    fields(
+        method = request.method().to_string(),
+        uri = request.uri().to_string(),
+        request_id = request_id(&request)
+    )
 )]
 pub async fn logging_middleware(request: Request<Body>, next: Next) -> Response {
+    telemetry::set_parent_from_headers(&tracing::Span::current(), request.headers());
     tracing::trace!("received a {} request to {}", request.method(), request.uri());
     next.run(request).await
 }
 
-async fn heartbeat_handler(Path(id): Path<u32>) -> Result<impl IntoResponse, ApiError> {
+// records the request count and latency labeled by the matched route
+pub async fn metrics_middleware(request: Request<Body>, next: Next) -> Response {
+    let started = Instant::now();
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|matched_path| matched_path.as_str().to_owned())
+        .unwrap_or_else(|| "unmatched".to_owned());
+
+    let response = next.run(request).await;
+
+    metrics_service::record_request(&method, &route, response.status().as_u16(), started.elapsed());
+    response
+}
+
+// completes the RFC 7807 problem details of error responses with the request path and id
+pub async fn problem_details_middleware(request: Request<Body>, next: Next) -> Response {
+    let instance = request.uri().path().to_owned();
+    let request_id = request_id(&request).to_owned();
+    let response = next.run(request).await;
+
+    let Some(api_error) = response.extensions().get::<ApiError>() else {
+        return response;
+    };
+    let mut problem = ProblemDetails::from(api_error).with_instance(instance);
+    if !request_id.is_empty() {
+        problem = problem.with_extension("request_id", request_id);
+    }
+
+    let (mut parts, _) = response.into_parts();
+    parts.headers.remove(CONTENT_LENGTH);
+    let problem_response = problem.into_response();
+    let (problem_parts, body) = problem_response.into_parts();
+    parts.headers.extend(problem_parts.headers);
+    Response::from_parts(parts, body)
+}
+
+#[utoipa::path(
+    get,
+    path = "/heartbeat/{id}",
+    tag = "service",
+    params(("id" = u32, Path, description = "Heartbeat id echoed in the response")),
+    responses(
+        (status = 200, description = "Service name and version", body = HashMap<String, String>),
+        (status = 400, description = "Invalid heartbeat id", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+pub async fn heartbeat_handler(Path(id): Path<u32>) -> Result<impl IntoResponse, ApiError> {
     tracing::trace!("heartbeat: received id: {}", id);
     let map = HashMap::from([
         ("service".to_string(), SERVICE_NAME.to_string()),
@@ -54,6 +139,20 @@ async fn heartbeat_handler(Path(id): Path<u32>) -> Result<impl IntoResponse, Api
     Ok(Json(map))
 }
 
+// the request id set by the `SetRequestIdLayer`
+fn request_id(request: &Request<Body>) -> &str {
+    request
+        .headers()
+        .get(X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default()
+}
+
+async fn metrics_handler(State(state): State<SharedState>) -> impl IntoResponse {
+    let metrics = metrics_service::render(&state);
+    ([(CONTENT_TYPE, "text/plain; version=0.0.4")], metrics)
+}
+
 async fn head_request_handler(method: Method) -> Response {
     // it usually only makes sense to special-case HEAD
     // if computing the body has some relevant cost
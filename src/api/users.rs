@@ -2,32 +2,138 @@ use axum::{
     extract::State,
     http::StatusCode,
     response::IntoResponse,
-    routing::{ delete, get, post, put },
+    routing::{ delete, get, patch, post, put },
     Router,
 };
+use chrono::NaiveDateTime;
+use serde::{ Deserialize, Serialize };
 use sqlx::types::Uuid;
+use utoipa::{ OpenApi, ToSchema };
 
 use crate::{
     application::{
         api_error::{ ApiError, ApiErrorType },
         api_json::Json,
         api_path::Path,
+        api_problem::ProblemDetails,
+        api_validation::{ is_valid_email, FieldError },
+        audit_service::{ self, AuditEvent },
+        email_verification_service,
         repository::user_repo,
-        security::jwt_claims::{ AccessClaims, ClaimsMethods },
+        security::{
+            auth_error::AuthError,
+            jwt_auth,
+            jwt_claims::{ AccessClaims, ClaimsMethods },
+            password,
+            password_policy,
+        },
         state::SharedState,
     },
     domain::models::user::User,
+    infrastructure::telemetry,
 };
 
+use super::auth::{ tokens_to_response, TokensResponse };
+
+// the user as seen by the user, without the password hash
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub(crate) struct UserProfile {
+    id: Uuid,
+    username: String,
+    email: String,
+    roles: String,
+    created_at: Option<NaiveDateTime>,
+    updated_at: Option<NaiveDateTime>,
+    email_verified_at: Option<NaiveDateTime>,
+}
+
+impl From<User> for UserProfile {
+    fn from(user: User) -> Self {
+        UserProfile {
+            id: user.id,
+            username: user.username,
+            email: user.email,
+            roles: user.roles,
+            created_at: user.created_at,
+            updated_at: user.updated_at,
+            email_verified_at: user.email_verified_at,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct UpdateProfile {
+    username: Option<String>,
+    email: Option<String>,
+    /// Required to change the email
+    current_password: Option<String>,
+}
+
+// the password is never printed
+impl std::fmt::Debug for UpdateProfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UpdateProfile")
+            .field("username", &self.username)
+            .field("email", &self.email)
+            .field("current_password", &self.current_password.as_ref().map(|_| telemetry::REDACTED))
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+struct ChangePassword {
+    current_password: String,
+    new_password: String,
+}
+
+// the passwords are never printed
+impl std::fmt::Debug for ChangePassword {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ChangePassword")
+            .field("current_password", &telemetry::REDACTED)
+            .field("new_password", &telemetry::REDACTED)
+            .finish()
+    }
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        list_users_handler,
+        add_user_handler,
+        get_user_handler,
+        update_user_handler,
+        delete_user_handler,
+        get_me_handler,
+        update_me_handler,
+        change_password_handler
+    ),
+    tags((name = "users", description = "User management, requires the admin role except for the own profile of the user"))
+)]
+pub struct UsersApi;
+
 pub fn routes() -> Router<SharedState> {
     Router::new()
         .route("/", get(list_users_handler))
         .route("/", post(add_user_handler))
+        .route("/me", get(get_me_handler))
+        .route("/me", patch(update_me_handler))
+        .route("/me/password", post(change_password_handler))
         .route("/:id", get(get_user_handler))
         .route("/:id", put(update_user_handler))
         .route("/:id", delete(delete_user_handler))
 }
 
+#[utoipa::path(
+    get,
+    path = "",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "List of users", body = [User]),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn list_users_handler(
     access_claims: AccessClaims,
     State(state): State<SharedState>
@@ -40,6 +146,18 @@ async fn list_users_handler(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = User,
+    responses(
+        (status = 201, description = "User created", body = User),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid email or password rejected by the policy", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn add_user_handler(
     access_claims: AccessClaims,
     State(state): State<SharedState>,
@@ -47,12 +165,31 @@ async fn add_user_handler(
 ) -> Result<impl IntoResponse, ApiError> {
     tracing::trace!("authentication details: {:#?}", access_claims);
     access_claims.validate_role_admin()?;
+    validate_user(&user, true, &state)?;
     match user_repo::add_user(user, &state).await {
-        Some(user) => Ok((StatusCode::CREATED, Json(user))),
+        Some(user) => {
+            state.shutdown.spawn_task(
+                "email verification mail",
+                email_verification_service::send_verification(user.clone(), state.clone())
+            );
+            Ok((StatusCode::CREATED, Json(user)))
+        }
         None => Err(StatusCode::INTERNAL_SERVER_ERROR.into()),
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/{id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User found", body = User),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn get_user_handler(
     access_claims: AccessClaims,
     Path(id): Path<Uuid>,
@@ -67,21 +204,64 @@ async fn get_user_handler(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/{id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    request_body = User,
+    responses(
+        (status = 200, description = "User updated", body = User),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid email or password rejected by the policy", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn update_user_handler(
     access_claims: AccessClaims,
     Path(id): Path<Uuid>,
     State(state): State<SharedState>,
-    Json(user): Json<User>
+    Json(mut user): Json<User>
 ) -> Result<Json<User>, ApiError> {
     tracing::trace!("authentication details: {:#?}", access_claims);
     tracing::trace!("id: {}", id);
     access_claims.validate_role_admin()?;
+    let Some(stored_user) = user_repo::get_user(id, &state).await else {
+        return Err(StatusCode::NOT_FOUND.into());
+    };
+    // the stored hash is sent back unchanged, any other value is a new password
+    let password_changed = user.password != stored_user.password;
+    validate_user(&user, password_changed, &state)?;
+    if password_changed {
+        user.password = password::hash_password(user.password.as_bytes(), &state.config());
+    }
     match user_repo::update_user(id, user, &state).await {
-        Some(user) => Ok(Json(user)),
+        Some(user) => {
+            if user.email != stored_user.email {
+                state.shutdown.spawn_task(
+                    "email verification mail",
+                    email_verification_service::send_verification(user.clone(), state.clone())
+                );
+            }
+            Ok(Json(user))
+        }
         None => Err(StatusCode::NOT_FOUND.into()),
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/{id}",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    params(("id" = Uuid, Path, description = "User id")),
+    responses(
+        (status = 200, description = "User deleted"),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "User not found", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
 async fn delete_user_handler(
     access_claims: AccessClaims,
     Path(id): Path<Uuid>,
@@ -101,3 +281,171 @@ async fn delete_user_handler(
         None => Err(StatusCode::INTERNAL_SERVER_ERROR.into()),
     }
 }
+
+#[utoipa::path(
+    get,
+    path = "/me",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "Profile of the user of the access token", body = UserProfile),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn get_me_handler(
+    access_claims: AccessClaims,
+    State(state): State<SharedState>
+) -> Result<Json<UserProfile>, ApiError> {
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let user = current_user(&access_claims, &state).await?;
+    Ok(Json(user.into()))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/me",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = UpdateProfile,
+    responses(
+        (status = 200, description = "Profile updated", body = UserProfile),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Invalid or taken username or email, or a missing or wrong current password for the email change", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn update_me_handler(
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Json(profile): Json<UpdateProfile>
+) -> Result<Json<UserProfile>, ApiError> {
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    tracing::trace!("profile: {:?}", profile);
+    let user = current_user(&access_claims, &state).await?;
+    let username = profile.username.map_or(user.username, |username| username.trim().to_string());
+    let email = profile.email.map_or_else(|| user.email.clone(), |email| email.trim().to_string());
+
+    // changing the email hands over the account recovery, so the password is asked again
+    if email != user.email {
+        let error = match profile.current_password {
+            None => Some(FieldError::new("current_password", "required", "is required to change the email")),
+            Some(current_password) => password::verify_password(&user.password, current_password.as_bytes(), &state.config())
+                .err()
+                .map(|_| FieldError::new("current_password", "mismatch", "does not match the current password")),
+        };
+        if let Some(error) = error {
+            return Err(vec![error].into());
+        }
+    }
+
+    let mut errors = Vec::new();
+    if username.is_empty() {
+        errors.push(FieldError::new("username", "required", "must not be empty"));
+    } else if let Some(other) = user_repo::get_user_by_username(&username, &state).await {
+        if other.id != user.id {
+            errors.push(FieldError::new("username", "taken", "is already in use"));
+        }
+    }
+    if !is_valid_email(&email) {
+        errors.push(FieldError::new("email", "invalid", "must be a valid email address"));
+    } else if let Some(other) = user_repo::get_user_by_email(&email, &state).await {
+        if other.id != user.id {
+            errors.push(FieldError::new("email", "taken", "is already in use"));
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    match user_repo::update_profile(user.id, &username, &email, &state).await {
+        Some(updated) => {
+            // the new email is verified again
+            if updated.email != user.email {
+                state.shutdown.spawn_task(
+                    "email verification mail",
+                    email_verification_service::send_verification(updated.clone(), state.clone())
+                );
+            }
+            Ok(Json(updated.into()))
+        }
+        None => Err(StatusCode::INTERNAL_SERVER_ERROR.into()),
+    }
+}
+
+// changing the password signs out the other devices, the current one gets new tokens
+#[utoipa::path(
+    post,
+    path = "/me/password",
+    tag = "users",
+    security(("bearer_auth" = [])),
+    request_body = ChangePassword,
+    responses(
+        (status = 200, description = "Password changed, the other sessions revoked and new tokens issued", body = TokensResponse),
+        (status = 401, description = "Wrong credentials", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 422, description = "Wrong current password or new password rejected by the policy", body = ProblemDetails, content_type = "application/problem+json")
+    )
+)]
+async fn change_password_handler(
+    access_claims: AccessClaims,
+    State(state): State<SharedState>,
+    Json(change): Json<ChangePassword>
+) -> Result<impl IntoResponse, ApiError> {
+    tracing::trace!("authentication details: {:#?}", access_claims);
+    let user = current_user(&access_claims, &state).await?;
+    let config = state.config();
+    if password::verify_password(&user.password, change.current_password.as_bytes(), &config).is_err() {
+        let error = FieldError::new("current_password", "mismatch", "does not match the current password");
+        return Err(vec![error].into());
+    }
+    let errors = password_policy::validate(
+        "new_password",
+        &change.new_password,
+        &user.username,
+        &user.email,
+        &config,
+        &state.breached_passwords
+    );
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let new_hash = password::hash_password(change.new_password.as_bytes(), &config);
+    if !user_repo::update_password(user.id, &new_hash, &state).await {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    audit_service::record(AuditEvent::PasswordChanged { user_id: &access_claims.sub });
+    let tokens = jwt_auth::reissue(user, &state).await?;
+    Ok(tokens_to_response(tokens))
+}
+
+// the user of the access token, the deleted and the deactivated users are rejected
+async fn current_user(access_claims: &AccessClaims, state: &SharedState) -> Result<User, ApiError> {
+    let id: Uuid = access_claims.sub.parse().map_err(|_| AuthError::InvalidToken)?;
+    match user_repo::get_user(id, state).await {
+        Some(user) if user.active => Ok(user),
+        _ => Err(AuthError::WrongCredentials.into()),
+    }
+}
+
+// the email and a new password of the user
+fn validate_user(user: &User, new_password: bool, state: &SharedState) -> Result<(), ApiError> {
+    let mut errors = Vec::new();
+    if !is_valid_email(&user.email) {
+        errors.push(FieldError::new("email", "invalid", "must be a valid email address"));
+    }
+    if new_password {
+        errors.extend(
+            password_policy::validate(
+                "password",
+                &user.password,
+                &user.username,
+                &user.email,
+                &state.config(),
+                &state.breached_passwords
+            )
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    Ok(())
+}
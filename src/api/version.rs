@@ -0,0 +1,171 @@
+use axum::{
+    body::Body,
+    extract::{ Request, State },
+    http::{ HeaderName, HeaderValue, StatusCode, Uri },
+    middleware::Next,
+    response::{ IntoResponse, Response },
+};
+use chrono::{ DateTime, Utc };
+
+use super::router::VERSION_ROUTES;
+
+use crate::application::{ api_error::{ ApiError, ApiErrorType }, state::SharedState };
+
+pub const ACCEPT_VERSION_HEADER: HeaderName = HeaderName::from_static("accept-version");
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ApiVersion {
+    V1,
+}
+
+/// Lifecycle of a published API version.
+/// A deprecated version is still served with `Deprecation` ([RFC9745](https://datatracker.ietf.org/doc/html/rfc9745))
+/// and `Sunset` ([RFC8594](https://datatracker.ietf.org/doc/html/rfc8594)) headers,
+/// after the sunset date its routes respond with `410 Gone`.
+#[derive(Debug, Clone, Copy)]
+pub struct ApiVersionInfo {
+    pub version: ApiVersion,
+    pub deprecated_at: Option<DateTime<Utc>>,
+    pub sunset_at: Option<DateTime<Utc>>,
+}
+
+// the list of the served API versions, the last one is the current version
+pub const API_VERSIONS: &[ApiVersionInfo] = &[
+    ApiVersionInfo {
+        version: ApiVersion::V1,
+        deprecated_at: None,
+        sunset_at: None,
+    },
+];
+
+impl ApiVersion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    pub fn prefix(&self) -> String {
+        format!("/{}", self.as_str())
+    }
+
+    pub fn current() -> ApiVersion {
+        API_VERSIONS.last().unwrap().version
+    }
+
+    pub fn info(&self) -> ApiVersionInfo {
+        *API_VERSIONS.iter()
+            .find(|info| info.version == *self)
+            .unwrap()
+    }
+
+    // accepts both `v1` and `1` notations
+    pub fn parse(value: &str) -> Option<ApiVersion> {
+        let value = value.trim();
+        let value = value.strip_prefix('v').unwrap_or(value);
+        API_VERSIONS.iter()
+            .map(|info| info.version)
+            .find(|version| &version.as_str()[1..] == value)
+    }
+
+    fn from_path(path: &str) -> Option<ApiVersion> {
+        let segment = path.trim_start_matches('/').split('/').next()?;
+        API_VERSIONS.iter()
+            .map(|info| info.version)
+            .find(|version| version.as_str() == segment)
+    }
+}
+
+// adds the deprecation headers to the responses of a deprecated version
+// and rejects requests to a version that is past its sunset date
+pub async fn version_headers_middleware(
+    State(version): State<ApiVersion>,
+    request: Request<Body>,
+    next: Next
+) -> Response {
+    let info = version.info();
+
+    if let Some(sunset_at) = info.sunset_at {
+        if sunset_at <= Utc::now() {
+            tracing::debug!("request to a retired api version: {}", version.as_str());
+            return ApiError {
+                status_code: StatusCode::GONE,
+                error_type: ApiErrorType::Api,
+                error_message: format!(
+                    "API version {} was retired, use {}",
+                    version.as_str(),
+                    ApiVersion::current().as_str()
+                ),
+            }.into_response();
+        }
+    }
+
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    if let Some(deprecated_at) = info.deprecated_at {
+        let value = format!("@{}", deprecated_at.timestamp());
+        headers.insert(DEPRECATION_HEADER, HeaderValue::from_str(&value).unwrap());
+    }
+    if let Some(sunset_at) = info.sunset_at {
+        let value = sunset_at.format("%a, %d %b %Y %H:%M:%S GMT").to_string();
+        headers.insert(SUNSET_HEADER, HeaderValue::from_str(&value).unwrap());
+    }
+    response
+}
+
+// routes an unversioned request to the version requested in the `Accept-Version` header,
+// this middleware rewrites the uri, so it should wrap the router instead of being a router layer
+pub async fn accept_version_middleware(
+    State(state): State<SharedState>,
+    mut request: Request<Body>,
+    next: Next
+) -> Response {
+    let path = request.uri().path();
+    if
+        !state.config().api_version_negotiation ||
+        ApiVersion::from_path(path).is_some() ||
+        !is_version_route(path)
+    {
+        return next.run(request).await;
+    }
+
+    let Some(accept_version) = request.headers().get(ACCEPT_VERSION_HEADER) else {
+        return next.run(request).await;
+    };
+
+    let Ok(accept_version) = accept_version.to_str() else {
+        tracing::debug!("invalid api version requested: {:?}", accept_version);
+        return unsupported_version("Invalid Accept-Version header".to_string());
+    };
+    let Some(version) = ApiVersion::parse(accept_version) else {
+        tracing::debug!("unsupported api version requested: {}", accept_version);
+        return unsupported_version(format!("Unsupported API version: {}", accept_version.trim()));
+    };
+
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(|path_and_query| path_and_query.as_str())
+        .unwrap_or("/");
+    let uri = format!("{}{}", version.prefix(), path_and_query);
+    tracing::trace!("accept-version: {} rewritten to {}", request.uri(), uri);
+    *request.uri_mut() = uri.parse::<Uri>().unwrap();
+
+    next.run(request).await
+}
+
+// the unversioned routes like `/health` or `/metrics` are never rewritten
+fn is_version_route(path: &str) -> bool {
+    let segment = path.trim_start_matches('/').split('/').next().unwrap_or_default();
+    VERSION_ROUTES.contains(&segment)
+}
+
+fn unsupported_version(error_message: String) -> Response {
+    ApiError {
+        status_code: StatusCode::BAD_REQUEST,
+        error_type: ApiErrorType::Api,
+        error_message,
+    }.into_response()
+}
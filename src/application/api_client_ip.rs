@@ -0,0 +1,39 @@
+use axum::{
+    async_trait,
+    extract::{ ConnectInfo, FromRef, FromRequestParts },
+    http::{ request::Parts, Extensions, HeaderMap },
+};
+use std::{ convert::Infallible, net::{ IpAddr, SocketAddr } };
+
+use super::{ config::Config, state::SharedState };
+
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// The ip address of the client, unknown if the connection info is missing
+#[derive(Debug, Clone, Copy)]
+pub struct ClientIp(pub Option<IpAddr>);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp where SharedState: FromRef<S>, S: Send + Sync {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let state = SharedState::from_ref(state);
+        Ok(Self(client_ip(&parts.headers, &parts.extensions, &state.config())))
+    }
+}
+
+/// Behind a reverse proxy the client is the last address appended to `X-Forwarded-For` by the proxy
+pub fn client_ip(headers: &HeaderMap, extensions: &Extensions, config: &Config) -> Option<IpAddr> {
+    if config.service_trust_forwarded_for {
+        let forwarded_for = headers
+            .get(X_FORWARDED_FOR)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        if forwarded_for.is_some() {
+            return forwarded_for;
+        }
+    }
+    extensions.get::<ConnectInfo<SocketAddr>>().map(|ConnectInfo(addr)| addr.ip())
+}
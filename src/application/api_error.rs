@@ -1,19 +1,29 @@
 use axum::{
-    extract::{ path::ErrorKind, rejection::{ JsonRejection, PathRejection } },
+    extract::{
+        path::ErrorKind,
+        rejection::{ FormRejection, JsonRejection, PathRejection, QueryRejection },
+    },
     http::StatusCode,
     response::{ IntoResponse, Response },
-    Json,
 };
 use serde::Serialize;
-use serde_json::json;
 
-use super::{ api_path::PathError, security::auth_error::AuthError };
+use super::{
+    api_path::PathError,
+    api_problem::ProblemDetails,
+    api_query::{ FormError, QueryError },
+    api_validation::FieldError,
+    security::auth_error::AuthError,
+};
 
 #[derive(Debug, Clone, Serialize)]
 pub enum ApiErrorType {
     Auth(AuthError),
     Api,
     Path(PathError),
+    Query(QueryError),
+    Form(FormError),
+    Validation(Vec<FieldError>),
 }
 
 #[derive(Debug, Clone)]
@@ -33,10 +43,11 @@ impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
         tracing::error!("Error response: {}", self.to_string());
 
-        (
-            self.status_code,
-            Json(json!({"type": self.error_type,"message": self.error_message})),
-        ).into_response()
+        // the error is kept in the response extensions,
+        // so the middleware can enrich the problem details with the request context
+        let mut response = ProblemDetails::from(&self).into_response();
+        response.extensions_mut().insert(self);
+        response
     }
 }
 
@@ -138,3 +149,36 @@ impl From<PathRejection> for ApiError {
         }
     }
 }
+
+impl From<QueryRejection> for ApiError {
+    fn from(rejection: QueryRejection) -> Self {
+        let error_type = match rejection {
+            QueryRejection::FailedToDeserializeQueryString(_) =>
+                QueryError::FailedToDeserializeQueryString,
+            _ => QueryError::UnhandledRejection,
+        };
+        ApiError {
+            status_code: rejection.status(),
+            error_type: ApiErrorType::Query(error_type),
+            error_message: rejection.body_text(),
+        }
+    }
+}
+
+impl From<FormRejection> for ApiError {
+    fn from(rejection: FormRejection) -> Self {
+        let error_type = match rejection {
+            FormRejection::InvalidFormContentType(_) => FormError::InvalidFormContentType,
+            FormRejection::FailedToDeserializeForm(_) => FormError::FailedToDeserializeForm,
+            FormRejection::FailedToDeserializeFormBody(_) =>
+                FormError::FailedToDeserializeFormBody,
+            FormRejection::BytesRejection(_) => FormError::FailedToBufferBody,
+            _ => FormError::UnhandledRejection,
+        };
+        ApiError {
+            status_code: rejection.status(),
+            error_type: ApiErrorType::Form(error_type),
+            error_message: rejection.body_text(),
+        }
+    }
+}
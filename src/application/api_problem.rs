@@ -0,0 +1,214 @@
+use axum::{
+    http::{ header::CONTENT_TYPE, HeaderValue },
+    response::{ IntoResponse, Response },
+};
+use serde::Serialize;
+use serde_json::{ Map, Value };
+use utoipa::ToSchema;
+
+use super::{
+    api_error::{ ApiError, ApiErrorType },
+    api_path::PathError,
+    api_query::{ FormError, QueryError },
+    app_const::API_PROBLEM_TYPE_BASE_URI,
+    security::auth_error::AuthError,
+};
+
+pub const PROBLEM_JSON_CONTENT_TYPE: &str = "application/problem+json";
+
+/// [Problem Details for HTTP APIs]
+/// [RFC7807](https://datatracker.ietf.org/doc/html/rfc7807#section-3)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    /// URI reference that identifies the problem type
+    #[serde(rename = "type")]
+    pub type_uri: String,
+    /// Short, human-readable summary of the problem type
+    pub title: String,
+    /// HTTP status code
+    pub status: u16,
+    /// Human-readable explanation specific to this occurrence
+    pub detail: String,
+    /// URI reference that identifies the specific occurrence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub instance: Option<String>,
+    /// Extension members
+    #[serde(flatten)]
+    #[schema(value_type = Object)]
+    pub extensions: Map<String, Value>,
+}
+
+/// An entry of the error catalog: a stable, machine-readable code and its title
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProblemType {
+    pub code: String,
+    pub title: String,
+}
+
+impl ProblemType {
+    fn new(code: &str, title: &str) -> Self {
+        Self {
+            code: code.to_owned(),
+            title: title.to_owned(),
+        }
+    }
+
+    pub fn type_uri(&self) -> String {
+        format!("{}{}", API_PROBLEM_TYPE_BASE_URI, self.code)
+    }
+}
+
+impl ProblemDetails {
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    pub fn with_extension(mut self, key: &str, value: impl Into<Value>) -> Self {
+        self.extensions.insert(key.to_owned(), value.into());
+        self
+    }
+}
+
+impl From<&ApiError> for ProblemDetails {
+    fn from(error: &ApiError) -> Self {
+        let problem_type = problem_type(&error.error_type, error.status_code.as_u16());
+
+        let mut extensions = Map::new();
+        extensions.insert("code".to_owned(), Value::from(problem_type.code.clone()));
+        if let ApiErrorType::Path(path_error) = &error.error_type {
+            match path_error {
+                PathError::ParseErrorAtKey(key) | PathError::InvalidUtf8InPathParam(key) => {
+                    extensions.insert("key".to_owned(), Value::from(key.clone()));
+                }
+                PathError::ParseErrorAtIndex(index) => {
+                    extensions.insert("index".to_owned(), Value::from(*index));
+                }
+                _ => {}
+            }
+        }
+        if let ApiErrorType::Validation(errors) = &error.error_type {
+            extensions.insert("errors".to_owned(), serde_json::to_value(errors).unwrap_or_default());
+        }
+
+        ProblemDetails {
+            type_uri: problem_type.type_uri(),
+            title: problem_type.title,
+            status: error.status_code.as_u16(),
+            detail: error.error_message.clone(),
+            instance: None,
+            extensions,
+        }
+    }
+}
+
+impl IntoResponse for ProblemDetails {
+    fn into_response(self) -> Response {
+        let status_code = axum::http::StatusCode
+            ::from_u16(self.status)
+            .unwrap_or(axum::http::StatusCode::INTERNAL_SERVER_ERROR);
+
+        let mut response = (status_code, axum::Json(self)).into_response();
+        response
+            .headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(PROBLEM_JSON_CONTENT_TYPE));
+        response
+    }
+}
+
+/// Looks up the catalog entry for the given error type.
+/// Codes are part of the public API contract and must not be changed once published.
+pub fn problem_type(error_type: &ApiErrorType, status: u16) -> ProblemType {
+    match error_type {
+        ApiErrorType::Auth(auth_error) => auth_problem_type(auth_error),
+        ApiErrorType::Path(path_error) => path_problem_type(path_error),
+        ApiErrorType::Query(query_error) => query_problem_type(query_error),
+        ApiErrorType::Form(form_error) => form_problem_type(form_error),
+        ApiErrorType::Validation(_) => ProblemType::new("validation.failed", "Validation failed"),
+        ApiErrorType::Api => api_problem_type(status),
+    }
+}
+
+fn auth_problem_type(error: &AuthError) -> ProblemType {
+    match error {
+        AuthError::WrongCredentials =>
+            ProblemType::new("auth.wrong_credentials", "Wrong credentials"),
+        AuthError::MissingCredentials =>
+            ProblemType::new("auth.missing_credentials", "Missing credentials"),
+        AuthError::TokenCreation => ProblemType::new("auth.token_creation", "Token creation error"),
+        AuthError::InvalidToken => ProblemType::new("auth.invalid_token", "Invalid token"),
+        AuthError::ExpiredToken => ProblemType::new("auth.expired_token", "Expired token"),
+        AuthError::TooManyAttempts =>
+            ProblemType::new("auth.too_many_attempts", "Too many failed login attempts"),
+        AuthError::EmailNotVerified => ProblemType::new("auth.email_not_verified", "Email not verified"),
+    }
+}
+
+fn path_problem_type(error: &PathError) -> ProblemType {
+    match error {
+        PathError::WrongNumberOfParameters =>
+            ProblemType::new(
+                "path.wrong_number_of_parameters",
+                "Wrong number of path parameters"
+            ),
+        PathError::ParseErrorAtKey(_) =>
+            ProblemType::new("path.parse_error_at_key", "Invalid path parameter"),
+        PathError::ParseErrorAtIndex(_) =>
+            ProblemType::new("path.parse_error_at_index", "Invalid path parameter"),
+        PathError::ParseError => ProblemType::new("path.parse_error", "Invalid path parameter"),
+        PathError::InvalidUtf8InPathParam(_) =>
+            ProblemType::new("path.invalid_utf8", "Invalid UTF-8 in path parameter"),
+        PathError::UnsupportedType =>
+            ProblemType::new("path.unsupported_type", "Unsupported path parameter type"),
+        PathError::Message => ProblemType::new("path.message", "Invalid path parameter"),
+        PathError::MissingPathParams =>
+            ProblemType::new("path.missing_params", "Missing path parameters"),
+        PathError::UnhandledDeserialization =>
+            ProblemType::new("path.unhandled_deserialization", "Invalid path parameter"),
+        PathError::UnhandledRejection =>
+            ProblemType::new("path.unhandled_rejection", "Path rejection"),
+    }
+}
+
+fn query_problem_type(error: &QueryError) -> ProblemType {
+    match error {
+        QueryError::FailedToDeserializeQueryString =>
+            ProblemType::new("query.invalid_query_string", "Invalid query string"),
+        QueryError::UnhandledRejection =>
+            ProblemType::new("query.unhandled_rejection", "Query rejection"),
+    }
+}
+
+fn form_problem_type(error: &FormError) -> ProblemType {
+    match error {
+        FormError::InvalidFormContentType =>
+            ProblemType::new("form.invalid_content_type", "Invalid form content type"),
+        FormError::FailedToDeserializeForm =>
+            ProblemType::new("form.invalid_form", "Invalid form data"),
+        FormError::FailedToDeserializeFormBody =>
+            ProblemType::new("form.invalid_body", "Invalid form data"),
+        FormError::FailedToBufferBody =>
+            ProblemType::new("form.failed_to_buffer_body", "Failed to read request body"),
+        FormError::UnhandledRejection =>
+            ProblemType::new("form.unhandled_rejection", "Form rejection"),
+    }
+}
+
+fn api_problem_type(status: u16) -> ProblemType {
+    match status {
+        400 => ProblemType::new("api.bad_request", "Bad request"),
+        401 => ProblemType::new("api.unauthorized", "Unauthorized"),
+        403 => ProblemType::new("api.forbidden", "Forbidden"),
+        404 => ProblemType::new("api.not_found", "Not found"),
+        405 => ProblemType::new("api.method_not_allowed", "Method not allowed"),
+        406 => ProblemType::new("api.not_acceptable", "Not acceptable"),
+        409 => ProblemType::new("api.conflict", "Conflict"),
+        413 => ProblemType::new("api.payload_too_large", "Payload too large"),
+        415 => ProblemType::new("api.unsupported_media_type", "Unsupported media type"),
+        422 => ProblemType::new("api.unprocessable_entity", "Unprocessable entity"),
+        429 => ProblemType::new("api.too_many_requests", "Too many requests"),
+        500 => ProblemType::new("api.internal_error", "Internal server error"),
+        503 => ProblemType::new("api.service_unavailable", "Service unavailable"),
+        _ => ProblemType::new("api.error", "API error"),
+    }
+}
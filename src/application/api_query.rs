@@ -0,0 +1,60 @@
+use axum::{
+    async_trait,
+    extract::{ rejection::FormRejection, FromRequest, FromRequestParts, Request },
+    http::request::Parts,
+};
+use serde::{ de::DeserializeOwned, Serialize };
+
+use super::api_error::ApiError;
+
+#[derive(Debug, Clone, Serialize)]
+pub enum QueryError {
+    FailedToDeserializeQueryString,
+    UnhandledRejection,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum FormError {
+    InvalidFormContentType,
+    FailedToDeserializeForm,
+    FailedToDeserializeFormBody,
+    FailedToBufferBody,
+    UnhandledRejection,
+}
+
+pub struct Query<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequestParts<S>
+    for Query<T>
+    where
+        // these trait bounds are copied from `impl FromRequestParts for axum::extract::Query`
+        T: DeserializeOwned,
+        S: Send + Sync
+{
+    type Rejection = ApiError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Query::<T>::from_request_parts(parts, state).await {
+            Ok(value) => Ok(Self(value.0)),
+            Err(rejection) => Err(rejection.into()),
+        }
+    }
+}
+
+pub struct Form<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S>
+    for Form<T>
+    where axum::Form<T>: FromRequest<S, Rejection = FormRejection>, S: Send + Sync
+{
+    type Rejection = ApiError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::Form::<T>::from_request(req, state).await {
+            Ok(value) => Ok(Self(value.0)),
+            Err(rejection) => Err(rejection.into()),
+        }
+    }
+}
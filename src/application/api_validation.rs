@@ -0,0 +1,54 @@
+use axum::http::StatusCode;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use super::api_error::{ ApiError, ApiErrorType };
+
+/// A problem with a field of the request body, reported in the `errors` member of the problem details
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct FieldError {
+    /// the field of the request body
+    pub field: String,
+    /// a stable, machine-readable code, e.g. `too_short`
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, code: &str, message: impl Into<String>) -> Self {
+        Self {
+            field: field.to_owned(),
+            code: code.to_owned(),
+            message: message.into(),
+        }
+    }
+}
+
+impl From<Vec<FieldError>> for ApiError {
+    fn from(errors: Vec<FieldError>) -> Self {
+        let error_message = errors
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+        ApiError {
+            status_code: StatusCode::UNPROCESSABLE_ENTITY,
+            error_type: ApiErrorType::Validation(errors),
+            error_message,
+        }
+    }
+}
+
+/// A light check, the address is only proven by delivering a message to it
+pub fn is_valid_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) =>
+            !local.is_empty() &&
+                domain.contains('.') &&
+                !domain.starts_with('.') &&
+                !domain.ends_with('.') &&
+                !email.contains(char::is_whitespace) &&
+                !domain.contains('@'),
+        None => false,
+    }
+}
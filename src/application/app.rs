@@ -1,75 +1,264 @@
 use crate::{
-    api::router,
-    application::{ config, state::AppState },
-    infrastructure::{ postgres, redis },
+    api::{ cors, rate_limit, redirect, router, version },
+    application::{
+        config::{ self, Config },
+        config_service,
+        metrics_service,
+        security::{ breached_passwords::BreachedPasswords, password },
+        shutdown::Shutdown,
+        state::AppState,
+    },
+    infrastructure::{ captcha::Captcha, listener::{ self, Listener }, mailer::Mailer, postgres, redis, tls },
 };
-use std::sync::Arc;
-use hyper::{ header::{ ACCEPT, AUTHORIZATION, CONTENT_TYPE }, Method };
+use std::{ net::SocketAddr, sync::Arc, time::Duration };
+use axum::{ body::Body, extract::Request, ServiceExt };
+use hyper::body::Incoming;
 use sqlx::migrate;
-use tokio::{ signal, sync::{ oneshot, Mutex } };
-use tower_http::cors::{ Any, CorsLayer };
+use tokio::{ signal, sync::oneshot };
+use tower::ServiceBuilder;
+use tower_http::request_id::{ MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer };
 
 pub async fn start_server(api_ready: oneshot::Sender<()>) {
     // load configuration
     config::load();
-    let config = config::get();
+    start_server_with_config(config::shared(), api_ready).await;
+}
+
+/// Starts the server with the given configuration instead of the global one
+pub async fn start_server_with_config(config: Arc<Config>, api_ready: oneshot::Sender<()>) {
+    start_server_with_shutdown(config, Shutdown::new(), api_ready).await;
+}
+
+/// The implementations plugged into the application state in place of the ones selected by the configuration
+#[derive(Clone, Default)]
+pub struct Plugins {
+    /// e.g. an SMTP delivery
+    pub mailer: Option<Arc<dyn Mailer>>,
+    /// e.g. the verification API of a CAPTCHA provider
+    pub captcha: Option<Arc<dyn Captcha>>,
+}
+
+/// Starts the server, the shutdown is triggered by SIGINT, SIGTERM or by the given `Shutdown`.
+/// The `api_ready` sender is dropped without a signal if the server cannot be started.
+pub async fn start_server_with_shutdown(
+    config: Arc<Config>,
+    shutdown: Shutdown,
+    api_ready: oneshot::Sender<()>
+) {
+    start_server_with_plugins(config, shutdown, Plugins::default(), api_ready).await;
+}
+
+/// Starts the server like `start_server_with_shutdown`, with the given implementations plugged in
+pub async fn start_server_with_plugins(
+    config: Arc<Config>,
+    shutdown: Shutdown,
+    plugins: Plugins,
+    api_ready: oneshot::Sender<()>
+) {
+    // install the metrics recorder
+    metrics_service::install();
+
+    // hash the dummy password up front, so the first failed login is not slower
+    password::dummy_hash(&config);
 
     // connect to redis
-    let redis = redis::open(config).await;
+    let redis = redis::open(&config).await;
 
     // connect to postgres
-    let pgpool = postgres::pgpool(config).await;
+    let pgpool = postgres::pgpool(&config).await;
 
     // run migrations
     migrate!("src/infrastructure/postgres/migrations").run(&pgpool).await.unwrap();
 
-    // build a CORS layer
-    // see https://docs.rs/tower-http/latest/tower_http/cors/index.html
-    // for more details
-    let cors_layer = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods([Method::HEAD, Method::GET, Method::POST, Method::PATCH, Method::DELETE])
-        .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
-    // let cors_header_value = config.service_http_addr().parse::<HeaderValue>().unwrap();
-    // let cors_layer = CorsLayer::new()
-    //      .allow_origin(cors_header_value)
-    //      .allow_methods([
-    //          Method::HEAD,
-    //          Method::GET,
-    //          Method::POST,
-    //          Method::PATCH,
-    //          Method::DELETE,
-    //      ])
-    //      .allow_credentials(true)
-    //      .allow_headers([AUTHORIZATION, ACCEPT, CONTENT_TYPE]);
-
-    // get the listening address
-    let addr = config.service_socket_addr();
+    // load the TLS certificates if enabled
+    let rustls_config = match tls::rustls_config(&config) {
+        Ok(rustls_config) => rustls_config,
+        Err(e) => {
+            tracing::error!("Could not load TLS certificates: {}", e);
+            return;
+        }
+    };
+    let server_config = config.clone();
+
+    // load the breached passwords if configured
+    let breached_passwords = match breached_passwords(&config) {
+        Ok(breached_passwords) => breached_passwords,
+        Err(e) => {
+            tracing::error!("Could not load the breached passwords: {}", e);
+            return;
+        }
+    };
 
     // build the state
-    let shared_state = Arc::new(AppState {
-        pgpool,
-        redis: Mutex::new(redis),
+    let mut state = AppState::new(config, pgpool, redis, shutdown.clone(), breached_passwords);
+    if let Some(mailer) = plugins.mailer {
+        state = state.with_mailer(mailer);
+    }
+    if let Some(captcha) = plugins.captcha {
+        state = state.with_captcha(captcha);
+    }
+    let shared_state = Arc::new(state);
+
+    // trigger the shutdown on SIGINT or SIGTERM
+    let signal_shutdown = shutdown.clone();
+    shutdown.spawn_job("shutdown signal", async move {
+        shutdown_signal().await;
+        signal_shutdown.trigger();
     });
 
+    // reload the configuration on SIGHUP or when the configuration file changes
+    shutdown.spawn_job("config watch", config_service::watch(shared_state.clone()));
+
     // build the app
     let app = router
-        ::routes(shared_state)
-        .layer(cors_layer)
-        .layer(axum::middleware::from_fn(router::logging_middleware));
+        ::routes(shared_state.clone())
+        // the rejected requests are recorded by the metrics
+        .layer(
+            axum::middleware::from_fn_with_state(
+                shared_state.clone(),
+                rate_limit::rate_limit_middleware
+            )
+        )
+        .layer(axum::middleware::from_fn(router::metrics_middleware));
+
+    // the api version negotiation rewrites the request uri, so it has to run before the routing,
+    // the layers above it complete its problems with the request id as well
+    let app = ServiceBuilder::new()
+        // accept the `x-request-id` header of the caller or generate a new one,
+        // and echo it back in the response
+        .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+        .layer(PropagateRequestIdLayer::x_request_id())
+        .layer(axum::middleware::from_fn(router::logging_middleware))
+        // the CORS policy follows the configuration, see `api::cors`
+        .layer(
+            axum::middleware::from_fn_with_state(
+                cors::Cors::new(shared_state.clone()),
+                cors::cors_middleware
+            )
+        )
+        // the error responses are completed as problem details
+        .layer(axum::middleware::from_fn(router::problem_details_middleware))
+        .layer(
+            axum::middleware::from_fn_with_state(
+                shared_state.clone(),
+                version::accept_version_middleware
+            )
+        )
+        .service(app);
+
+    let pre_stop_delay = Duration::from_secs(server_config.shutdown_pre_stop_delay_seconds);
+    let drain_timeout = Duration::from_secs(server_config.shutdown_drain_timeout_seconds);
 
     // build the listener
-    let listener = tokio::net::TcpListener::bind(&addr).await.unwrap();
-    tracing::info!("listening on {}", addr);
+    let listener = match listener::bind(&server_config).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Could not bind the listener: {}", e);
+            return;
+        }
+    };
+    tracing::info!("listening on {}", listener);
+    #[cfg(unix)]
+    if
+        matches!(listener, Listener::Unix(_)) &&
+        server_config.rate_limit_enabled &&
+        !server_config.service_trust_forwarded_for
+    {
+        tracing::warn!(
+            "the client ip is unknown on a Unix socket, the requests are not rate limited by ip without SERVICE_TRUST_FORWARDED_FOR"
+        );
+    }
+
+    // redirect the plain HTTP requests to the HTTPS service
+    let mut redirect_server = None;
+    if let (Some(redirect_port), Some(addr)) = (server_config.tls_redirect_http_port, listener.tcp_addr()) {
+        let redirect_addr = SocketAddr::new(addr.ip(), redirect_port);
+        let redirect_listener = match tokio::net::TcpListener::bind(redirect_addr).await {
+            Ok(redirect_listener) => redirect_listener,
+            Err(e) => {
+                tracing::error!("Could not bind the HTTP redirect listener on {}: {}", redirect_addr, e);
+                return;
+            }
+        };
+        tracing::info!("redirecting {} to HTTPS", redirect_addr);
+        let shutdown = shutdown.clone();
+        redirect_server = Some(
+            tokio::spawn(async move {
+                listener::serve(
+                    redirect_listener,
+                    redirect::routes(addr.port()),
+                    shutdown.drain(pre_stop_delay),
+                    drain_timeout
+                ).await;
+            })
+        );
+    }
 
     api_ready.send(()).expect("Couild not send a ready signal");
 
     // start the service
-    axum::serve(listener, app).with_graceful_shutdown(shutdown_signal()).await.unwrap();
+    match (listener, rustls_config) {
+        (Listener::Tcp(listener), Some(rustls_config)) => {
+            // reload the renewed certificates
+            shutdown.spawn_job("certificate reload", tls::watch(rustls_config.clone(), server_config));
+
+            // axum-server passes the hyper body, converted here the same way `axum::serve` does
+            let app = tower::ServiceExt::map_request(app, |request: Request<Incoming>| {
+                request.map(Body::new)
+            });
+
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            let drain_shutdown = shutdown.clone();
+            tokio::spawn(async move {
+                drain_shutdown.drain(pre_stop_delay).await;
+                shutdown_handle.graceful_shutdown(Some(drain_timeout));
+            });
+            let served = match listener.into_std() {
+                Ok(listener) =>
+                    axum_server
+                        ::from_tcp_rustls(listener, rustls_config)
+                        .handle(handle)
+                        .serve(
+                            ServiceExt::<Request<Incoming>>::into_make_service_with_connect_info::<SocketAddr>(app)
+                        ).await,
+                Err(e) => Err(e),
+            };
+            // the redirect server and the background jobs are stopped as on a shutdown
+            if let Err(e) = served {
+                tracing::error!("The HTTPS server failed: {}", e);
+                shutdown.trigger();
+            }
+        }
+        (Listener::Tcp(listener), None) => {
+            listener::serve(listener, app, shutdown.drain(pre_stop_delay), drain_timeout).await;
+        }
+        // TLS on a Unix socket is rejected by the configuration validation and by `listener::bind`
+        #[cfg(unix)]
+        (Listener::Unix(socket), _) => {
+            listener::serve(socket, app, shutdown.drain(pre_stop_delay), drain_timeout).await;
+        }
+    }
+    if let Some(redirect_server) = redirect_server {
+        let _ = redirect_server.await;
+    }
+
+    // stop the background jobs and close the connections to the databases
+    shutdown.stop_jobs(drain_timeout).await;
+    shared_state.pgpool.close().await;
 
     tracing::info!("server shutdown successfully.");
 }
 
+fn breached_passwords(config: &Config) -> Result<BreachedPasswords, String> {
+    let Some(path) = &config.password_breached_corpus_path else {
+        return Ok(BreachedPasswords::default());
+    };
+    let breached_passwords = BreachedPasswords::load(path).map_err(|e| format!("{}: {}", path, e))?;
+    tracing::info!("{} breached passwords loaded from {}", breached_passwords.len(), path);
+    Ok(breached_passwords)
+}
+
 async fn shutdown_signal() {
     let ctrl_c = async {
         signal::ctrl_c().await.expect("failed to install Ctrl+C handler");
@@ -1,11 +1,31 @@
 pub const SERVICE_NAME: &str = "axum-web";
 pub const SERVICE_VERSION: &str = "1.0.0";
 
+// optional TOML configuration file, overridden by the `CONFIG_FILE` environment variable
+pub const CONFIG_FILE: &str = "config.toml";
+
+// request id header, accepted from the caller or generated
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+// rate limit headers of the responses
+pub const RATE_LIMIT_LIMIT: &str = "ratelimit-limit";
+pub const RATE_LIMIT_REMAINING: &str = "ratelimit-remaining";
+pub const RATE_LIMIT_RESET: &str = "ratelimit-reset";
+pub const RATE_LIMIT_REDIS_KEY_PREFIX: &str = "rate.limit";
+
+// login failure counters and blocks, by `user:{username}` or `ip:{address}`
+pub const LOGIN_FAILURES_REDIS_KEY_PREFIX: &str = "login.failures";
+pub const LOGIN_BLOCKED_REDIS_KEY_PREFIX: &str = "login.blocked";
+
+// RFC 7807 problem type URIs are built as `{API_PROBLEM_TYPE_BASE_URI}{code}`
+pub const API_PROBLEM_TYPE_BASE_URI: &str = "/problems/";
+
 // user roles
 pub const USER_ROLE_ADMIN: &str = "admin";
 pub const USER_ROLE_GUEST: &str = "guest";
 
 // JWT related constants
+pub const JWT_SECRET_MIN_LENGTH: usize = 32;
 pub const JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY: &str = "jwt.revoke.global.before";
 pub const JWT_REDIS_REVOKE_USER_BEFORE_KEY: &str = "jwt.revoke.user.before";
 pub const JWT_REDIS_REVOKED_TOKENS_KEY: &str = "jwt.revoked.tokens";
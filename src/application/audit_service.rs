@@ -0,0 +1,82 @@
+use std::net::IpAddr;
+
+// the audit events are logged to this target, so they can be filtered and routed separately
+pub const AUDIT_TARGET: &str = "axum_web::audit";
+
+/// The security relevant events, the passwords and tokens are never part of them
+#[derive(Debug, Clone)]
+pub enum AuditEvent<'a> {
+    LoginSucceeded {
+        username: &'a str,
+        ip: Option<IpAddr>,
+    },
+    LoginFailed {
+        username: &'a str,
+        ip: Option<IpAddr>,
+    },
+    /// a login attempt rejected while the username or the ip is blocked
+    LoginBlocked {
+        username: &'a str,
+        ip: Option<IpAddr>,
+        retry_after_seconds: u64,
+    },
+    /// subject: `user:{username}` or `ip:{address}`
+    LoginLocked {
+        subject: &'a str,
+        failures: u64,
+        lockout_seconds: u64,
+    },
+    LoginUnlocked {
+        subject: &'a str,
+        admin_id: &'a str,
+    },
+    /// the other sessions of the user are revoked
+    PasswordChanged {
+        user_id: &'a str,
+    },
+    /// a reset token is sent to the email of the user
+    PasswordResetRequested {
+        user_id: &'a str,
+    },
+    /// the password is set with a reset token, the sessions of the user are revoked
+    PasswordReset {
+        user_id: &'a str,
+    },
+    /// a verification token is sent to the email of the user
+    EmailVerificationSent {
+        user_id: &'a str,
+    },
+    EmailVerified {
+        user_id: &'a str,
+    },
+    UserRegistered {
+        user_id: &'a str,
+        ip: Option<IpAddr>,
+    },
+    InviteCreated {
+        admin_id: &'a str,
+    },
+}
+
+impl AuditEvent<'_> {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::LoginSucceeded { .. } => "login_succeeded",
+            Self::LoginFailed { .. } => "login_failed",
+            Self::LoginBlocked { .. } => "login_blocked",
+            Self::LoginLocked { .. } => "login_locked",
+            Self::LoginUnlocked { .. } => "login_unlocked",
+            Self::PasswordChanged { .. } => "password_changed",
+            Self::PasswordResetRequested { .. } => "password_reset_requested",
+            Self::PasswordReset { .. } => "password_reset",
+            Self::EmailVerificationSent { .. } => "email_verification_sent",
+            Self::EmailVerified { .. } => "email_verified",
+            Self::UserRegistered { .. } => "user_registered",
+            Self::InviteCreated { .. } => "invite_created",
+        }
+    }
+}
+
+pub fn record(event: AuditEvent) {
+    tracing::info!(target: AUDIT_TARGET, event = event.name(), details = ?event, "audit");
+}
@@ -1,14 +1,95 @@
 use core::fmt;
 use jsonwebtoken::{DecodingKey, EncodingKey};
-use std::{net::SocketAddr, sync::OnceLock};
+use std::{ collections::HashMap, net::SocketAddr, str::FromStr, sync::{ Arc, RwLock } };
 
-pub static CONFIG: OnceLock<Config> = OnceLock::new();
+use hyper::{ header::HeaderName, http::HeaderValue, Method };
+use tracing_subscriber::EnvFilter;
 
-#[derive(Debug)]
+use super::app_const::{ CONFIG_FILE, JWT_SECRET_MIN_LENGTH, SERVICE_NAME };
+
+// swapped on reload, when the application state was built from it
+static CONFIG: RwLock<Option<Arc<Config>>> = RwLock::new(None);
+
+#[derive(Clone)]
 pub struct Config {
+    // configuration file
+    pub config_watch_interval_seconds: u64,
+
     // service
     pub service_host: String,
     pub service_port: u16,
+    pub service_unix_socket: Option<String>,
+    pub service_unix_socket_mode: FileMode,
+    pub service_trust_forwarded_for: bool,
+    pub api_version_negotiation: bool,
+    pub health_check_timeout_milliseconds: u64,
+    pub shutdown_pre_stop_delay_seconds: u64,
+    pub shutdown_drain_timeout_seconds: u64,
+
+    // TLS
+    pub tls_cert_path: Option<String>,
+    pub tls_key_path: Option<String>,
+    pub tls_client_ca_path: Option<String>,
+    pub tls_client_auth_required: bool,
+    pub tls_reload_interval_seconds: u64,
+    pub tls_redirect_http_port: Option<u16>,
+
+    // CORS
+    pub cors_allowed_origins: Vec<String>,
+    pub cors_allowed_methods: Vec<String>,
+    pub cors_allowed_headers: Vec<String>,
+    pub cors_exposed_headers: Vec<String>,
+    pub cors_allow_credentials: bool,
+    pub cors_max_age_seconds: u64,
+    pub cors_public_routes: Vec<String>,
+
+    // rate limiting
+    pub rate_limit_enabled: bool,
+    pub rate_limit_store: RateLimitStore,
+    pub rate_limit_rules: Vec<RateLimitRule>,
+    pub rate_limit_api_key_header: String,
+    pub rate_limit_redis_timeout_milliseconds: u64,
+
+    // login lockout
+    pub login_lockout_enabled: bool,
+    pub login_failure_window_seconds: u64,
+    pub login_backoff_base_seconds: u64,
+    pub login_backoff_max_seconds: u64,
+    pub login_lockout_threshold: u64,
+    pub login_lockout_ip_threshold: u64,
+    pub login_lockout_seconds: u64,
+
+    // password hashing
+    pub password_hash_memory_kib: u32,
+    pub password_hash_iterations: u32,
+    pub password_hash_parallelism: u32,
+    pub password_pepper: Option<String>,
+    pub password_min_length: usize,
+    pub password_max_length: usize,
+    pub password_min_character_classes: usize,
+    pub password_breached_corpus_path: Option<String>,
+    pub password_reset_token_seconds: u64,
+    pub password_reset_url: Option<String>,
+
+    // email verification
+    pub email_verification_required: bool,
+    pub email_verification_token_seconds: u64,
+    pub email_verification_url: Option<String>,
+
+    // self-registration
+    pub registration_policy: RegistrationPolicy,
+    pub registration_allowed_domains: Vec<String>,
+    pub registration_invite_seconds: u64,
+    pub registration_auto_login: bool,
+
+    // CAPTCHA
+    pub captcha_kind: CaptchaKind,
+    pub captcha_static_token: Option<String>,
+
+    // mailer
+    pub mailer_kind: MailerKind,
+    pub mailer_file_dir: String,
+    pub mailer_from: String,
 
     // redis
     pub redis_host: String,
@@ -29,8 +110,314 @@ pub struct Config {
     pub jwt_expire_refresh_token_seconds: i64,
     pub jwt_validation_leeway_seconds: i64,
     pub jwt_enable_revoked_tokens: bool,
+
+    // logging
+    pub log_format: LogFormat,
+    pub log_filter: String,
+
+    // OpenTelemetry
+    pub otel_exporter_otlp_endpoint: Option<String>,
+    pub otel_service_name: String,
+}
+
+// the secrets are redacted, a field missing from the lists fails to compile
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        macro_rules! debug_config {
+            (plain: [$($plain:ident,)+] secrets: [$($secret:ident,)+] optional_secrets: [$($optional:ident,)+]) => {{
+                let Config { $($plain,)+ $($secret: _,)+ $($optional,)+ } = self;
+                let mut debug = f.debug_struct("Config");
+                $(debug.field(stringify!($plain), $plain);)+
+                $(debug.field(stringify!($secret), &Redacted);)+
+                $(debug.field(stringify!($optional), &$optional.as_ref().map(|_| Redacted));)+
+                debug.finish()
+            }};
+        }
+        debug_config!(
+            plain: [
+                config_watch_interval_seconds,
+                service_host,
+                service_port,
+                service_unix_socket,
+                service_unix_socket_mode,
+                service_trust_forwarded_for,
+                api_version_negotiation,
+                health_check_timeout_milliseconds,
+                shutdown_pre_stop_delay_seconds,
+                shutdown_drain_timeout_seconds,
+                tls_cert_path,
+                tls_key_path,
+                tls_client_ca_path,
+                tls_client_auth_required,
+                tls_reload_interval_seconds,
+                tls_redirect_http_port,
+                cors_allowed_origins,
+                cors_allowed_methods,
+                cors_allowed_headers,
+                cors_exposed_headers,
+                cors_allow_credentials,
+                cors_max_age_seconds,
+                cors_public_routes,
+                rate_limit_enabled,
+                rate_limit_store,
+                rate_limit_rules,
+                rate_limit_api_key_header,
+                rate_limit_redis_timeout_milliseconds,
+                login_lockout_enabled,
+                login_failure_window_seconds,
+                login_backoff_base_seconds,
+                login_backoff_max_seconds,
+                login_lockout_threshold,
+                login_lockout_ip_threshold,
+                login_lockout_seconds,
+                password_hash_memory_kib,
+                password_hash_iterations,
+                password_hash_parallelism,
+                password_min_length,
+                password_max_length,
+                password_min_character_classes,
+                password_breached_corpus_path,
+                password_reset_token_seconds,
+                password_reset_url,
+                email_verification_required,
+                email_verification_token_seconds,
+                email_verification_url,
+                registration_policy,
+                registration_allowed_domains,
+                registration_invite_seconds,
+                registration_auto_login,
+                captcha_kind,
+                mailer_kind,
+                mailer_file_dir,
+                mailer_from,
+                redis_host,
+                redis_port,
+                postgres_user,
+                postgres_host,
+                postgres_port,
+                postgres_db,
+                postgres_connection_pool,
+                jwt_keys,
+                jwt_expire_access_token_seconds,
+                jwt_expire_refresh_token_seconds,
+                jwt_validation_leeway_seconds,
+                jwt_enable_revoked_tokens,
+                log_format,
+                log_filter,
+                otel_exporter_otlp_endpoint,
+                otel_service_name,
+            ]
+            secrets: [
+                postgres_password,
+                jwt_secret,
+            ]
+            optional_secrets: [
+                password_pepper,
+                captcha_static_token,
+            ]
+        )
+    }
+}
+
+struct Redacted;
+
+impl fmt::Debug for Redacted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("[REDACTED]")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum LogFormat {
+    #[default]
+    Compact,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "compact" => Ok(Self::Compact),
+            "json" => Ok(Self::Json),
+            _ => Err(format!("unknown log format: {}", s)),
+        }
+    }
+}
+
+/// Where the rate limit counters are kept,
+/// the in-memory store is per instance and used as the fallback when Redis fails
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RateLimitStore {
+    #[default]
+    Redis,
+    Memory,
+}
+
+impl FromStr for RateLimitStore {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "redis" => Ok(Self::Redis),
+            "memory" => Ok(Self::Memory),
+            _ => Err(format!("unknown rate limit store: {}", s)),
+        }
+    }
 }
 
+/// How the messages are delivered, both kinds are meant for development and tests
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MailerKind {
+    /// written to the log
+    #[default]
+    Log,
+    /// written as files to `MAILER_FILE_DIR`
+    File,
+}
+
+impl FromStr for MailerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "log" => Ok(Self::Log),
+            "file" => Ok(Self::File),
+            _ => Err(format!("unknown mailer kind: {}", s)),
+        }
+    }
+}
+
+/// Who may register with `POST /auth/register`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum RegistrationPolicy {
+    /// only the admins create users
+    #[default]
+    Disabled,
+    Open,
+    /// with an invite code issued by an admin
+    InviteOnly,
+    /// with an email of the `REGISTRATION_ALLOWED_DOMAINS`
+    DomainAllowlist,
+}
+
+impl FromStr for RegistrationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "open" => Ok(Self::Open),
+            "invite_only" => Ok(Self::InviteOnly),
+            "domain_allowlist" => Ok(Self::DomainAllowlist),
+            _ => Err(format!("unknown registration policy: {}", s)),
+        }
+    }
+}
+
+/// How the CAPTCHA responses of the registrations are checked,
+/// the providers are plugged in with `app::Plugins`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum CaptchaKind {
+    /// not checked
+    #[default]
+    Disabled,
+    /// compared with `CAPTCHA_STATIC_TOKEN`, meant for development and tests
+    Static,
+}
+
+impl FromStr for CaptchaKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "disabled" => Ok(Self::Disabled),
+            "static" => Ok(Self::Static),
+            _ => Err(format!("unknown captcha kind: {}", s)),
+        }
+    }
+}
+
+/// What the requests are counted by, the anonymous requests are counted by ip
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitKey {
+    Ip,
+    /// the subject of a valid access token
+    User,
+    /// the value of the `RATE_LIMIT_API_KEY_HEADER` header
+    ApiKey,
+}
+
+/// The rate limit of the routes starting with the path: `<path>=<requests>/<seconds>[:ip|user|api_key]`,
+/// e.g. `/v1/auth/login=10/60:ip`, the rule of the longest matching path applies
+#[derive(Clone, PartialEq)]
+pub struct RateLimitRule {
+    pub path: String,
+    pub requests: u64,
+    pub window_seconds: u64,
+    pub key: RateLimitKey,
+}
+
+impl FromStr for RateLimitRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid rate limit rule: {}", s);
+        let (path, limit) = s.split_once('=').ok_or_else(invalid)?;
+        let (limit, key) = limit.split_once(':').unwrap_or((limit, "ip"));
+        let (requests, window_seconds) = limit.split_once('/').ok_or_else(invalid)?;
+        let rule = Self {
+            path: path.trim().to_string(),
+            requests: requests.trim().parse().map_err(|_| invalid())?,
+            window_seconds: window_seconds.trim().parse().map_err(|_| invalid())?,
+            key: match key.trim() {
+                "ip" => RateLimitKey::Ip,
+                "user" => RateLimitKey::User,
+                "api_key" => RateLimitKey::ApiKey,
+                _ => {
+                    return Err(invalid());
+                }
+            },
+        };
+        if !rule.path.starts_with('/') || rule.requests == 0 || rule.window_seconds == 0 {
+            return Err(invalid());
+        }
+        Ok(rule)
+    }
+}
+
+impl fmt::Debug for RateLimitRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let key = match self.key {
+            RateLimitKey::Ip => "ip",
+            RateLimitKey::User => "user",
+            RateLimitKey::ApiKey => "api_key",
+        };
+        write!(f, "{}={}/{}:{}", self.path, self.requests, self.window_seconds, key)
+    }
+}
+
+/// Unix file permissions, parsed from octal: `660`, `0660` or `0o660`
+#[derive(Clone, Copy, PartialEq)]
+pub struct FileMode(pub u32);
+
+impl FromStr for FileMode {
+    type Err = std::num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("0o").unwrap_or(s);
+        u32::from_str_radix(s, 8).map(Self)
+    }
+}
+
+impl fmt::Debug for FileMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#o}", self.0)
+    }
+}
+
+#[derive(Clone)]
 pub struct JwtKeys {
     pub encoding: EncodingKey,
     pub decoding: DecodingKey,
@@ -55,11 +442,15 @@ impl JwtKeys {
 
 impl Config {
     pub fn service_http_addr(&self) -> String {
-        format!("{}://{}:{}", "http", self.service_host, self.service_port)
+        let scheme = if self.tls_enabled() { "https" } else { "http" };
+        format!("{}://{}:{}", scheme, self.service_host, self.service_port)
+    }
+
+    pub fn tls_enabled(&self) -> bool {
+        self.tls_cert_path.is_some() && self.tls_key_path.is_some()
     }
 
     pub fn service_socket_addr(&self) -> SocketAddr {
-        use std::str::FromStr;
         SocketAddr::from_str(&format!("{}:{}", self.service_host, self.service_port)).unwrap()
     }
 
@@ -79,8 +470,60 @@ impl Config {
     }
 }
 
+/// Loads the configuration, reports every problem found and panics if there is any
 pub fn load() {
-    let env_file = if env_get_or("ENV_TEST", "0") == "1" {
+    match try_load() {
+        Ok(config) => {
+            tracing::trace!("configuration: {:#?}", config);
+            CONFIG.write().unwrap().get_or_insert_with(|| Arc::new(config));
+        }
+        Err(e) => {
+            tracing::error!("{}", e);
+            panic!("{e}");
+        }
+    }
+}
+
+/// The globally loaded configuration, a compatibility shim for the code without access
+/// to the application state, prefer `state.config`
+pub fn get() -> Arc<Config> {
+    shared()
+}
+
+/// The globally loaded configuration to be injected into the application state
+pub fn shared() -> Arc<Config> {
+    CONFIG.read().unwrap().clone().expect("the configuration is not loaded")
+}
+
+/// Swaps the global configuration with the reloaded one, if it is the `previous` configuration
+/// of the reloaded application state
+pub fn swap_shared(previous: &Arc<Config>, config: Arc<Config>) {
+    let mut shared = CONFIG.write().unwrap();
+    if shared.as_ref().is_some_and(|shared| Arc::ptr_eq(shared, previous)) {
+        *shared = Some(config);
+    }
+}
+
+/// The keys of the TOML file overridden by the environment (or the `.env` file),
+/// their changes in the file have no effect
+pub fn shadowed_keys() -> Vec<String> {
+    let Ok(contents) = std::fs::read_to_string(config_file()) else {
+        return Vec::new();
+    };
+    let is_set = |key: &str| std::env::var(key).is_ok_and(|value| !value.is_empty());
+    let mut keys: Vec<String> = ConfigSource::new(&contents, false)
+        .file.into_keys()
+        .filter(|key| is_set(key) || is_set(&format!("{}_FILE", key)))
+        .collect();
+    keys.sort();
+    keys
+}
+
+/// Loads the configuration layers, from the lowest to the highest priority:
+/// defaults, the TOML file (`CONFIG_FILE`, `config.toml` if present), the `.env` file
+/// and the environment, where `KEY_FILE` reads the value of `KEY` from a (secret) file.
+pub fn try_load() -> Result<Config, ConfigError> {
+    let env_file = if std::env::var("ENV_TEST").as_deref() == Ok("1") {
         ".env_test"
     } else {
         ".env"
@@ -93,64 +536,610 @@ pub fn load() {
         tracing::info!("{} file not found, using existing environment", env_file);
     }
 
-    let jwt_secret = env_get("JWT_SECRET");
+    let path = config_file();
+    match std::fs::read_to_string(&path) {
+        Ok(contents) => {
+            tracing::info!("{} file loaded", path);
+            from_toml(&contents)
+        }
+        Err(e) if std::env::var("CONFIG_FILE").is_ok() => {
+            Err(ConfigError(vec![format!("CONFIG_FILE {}: {}", path, e)]))
+        }
+        Err(_) => from_toml(""),
+    }
+}
+
+/// The path of the TOML configuration file
+pub fn config_file() -> String {
+    std::env::var("CONFIG_FILE").unwrap_or_else(|_| CONFIG_FILE.to_string())
+}
+
+/// Builds the configuration from the TOML contents overridden by the environment
+pub fn from_toml(contents: &str) -> Result<Config, ConfigError> {
+    build(ConfigSource::new(contents, true))
+}
+
+/// Builds the configuration from the TOML contents alone, the environment is not read
+pub fn from_toml_only(contents: &str) -> Result<Config, ConfigError> {
+    build(ConfigSource::new(contents, false))
+}
+
+fn build(mut source: ConfigSource) -> Result<Config, ConfigError> {
+    let jwt_secret: String = source.required("JWT_SECRET");
 
     // parse configuration
     let config = Config {
-        service_host: env_get("SERVICE_HOST"),
-        service_port: env_parse("SERVICE_PORT"),
-        redis_host: env_get("REDIS_HOST"),
-        redis_port: env_parse("REDIS_PORT"),
-        postgres_user: env_get("POSTGRES_USER"),
-        postgres_password: env_get("POSTGRES_PASSWORD"),
-        postgres_host: env_get("POSTGRES_HOST"),
-        postgres_port: env_parse("POSTGRES_PORT"),
-        postgres_db: env_get("POSTGRES_DB"),
-        postgres_connection_pool: env_parse("POSTGRES_CONNECTION_POOL"),
+        config_watch_interval_seconds: source.parse_or("CONFIG_WATCH_INTERVAL_SECONDS", 5),
+        service_host: source.parse_or("SERVICE_HOST", "127.0.0.1".to_string()),
+        service_port: source.parse_or("SERVICE_PORT", 3000),
+        service_unix_socket: source.optional("SERVICE_UNIX_SOCKET"),
+        service_unix_socket_mode: source.parse_or("SERVICE_UNIX_SOCKET_MODE", FileMode(0o660)),
+        service_trust_forwarded_for: source.parse_or("SERVICE_TRUST_FORWARDED_FOR", false),
+        api_version_negotiation: source.parse_or("API_VERSION_NEGOTIATION", true),
+        health_check_timeout_milliseconds: source.parse_or(
+            "HEALTH_CHECK_TIMEOUT_MILLISECONDS",
+            1000
+        ),
+        shutdown_pre_stop_delay_seconds: source.parse_or("SHUTDOWN_PRE_STOP_DELAY_SECONDS", 0),
+        shutdown_drain_timeout_seconds: source.parse_or("SHUTDOWN_DRAIN_TIMEOUT_SECONDS", 30),
+        tls_cert_path: source.optional("TLS_CERT_PATH"),
+        tls_key_path: source.optional("TLS_KEY_PATH"),
+        tls_client_ca_path: source.optional("TLS_CLIENT_CA_PATH"),
+        tls_client_auth_required: source.parse_or("TLS_CLIENT_AUTH_REQUIRED", true),
+        tls_reload_interval_seconds: source.parse_or("TLS_RELOAD_INTERVAL_SECONDS", 60),
+        tls_redirect_http_port: source.parse_optional("TLS_REDIRECT_HTTP_PORT"),
+        cors_allowed_origins: source.list_or("CORS_ALLOWED_ORIGINS", "*"),
+        cors_allowed_methods: source.list_or("CORS_ALLOWED_METHODS", "GET,HEAD,POST,PATCH,DELETE"),
+        cors_allowed_headers: source.list_or(
+            "CORS_ALLOWED_HEADERS",
+            "authorization,accept,content-type,accept-version,x-request-id"
+        ),
+        cors_exposed_headers: source.list_or(
+            "CORS_EXPOSED_HEADERS",
+            "x-request-id,deprecation,sunset,retry-after,ratelimit-limit,ratelimit-remaining,ratelimit-reset"
+        ),
+        cors_allow_credentials: source.parse_or("CORS_ALLOW_CREDENTIALS", false),
+        cors_max_age_seconds: source.parse_or("CORS_MAX_AGE_SECONDS", 3600),
+        cors_public_routes: source.list_or("CORS_PUBLIC_ROUTES", "/v1/heartbeat,/health"),
+        redis_host: source.parse_or("REDIS_HOST", "127.0.0.1".to_string()),
+        redis_port: source.parse_or("REDIS_PORT", 6379),
+        postgres_user: source.required("POSTGRES_USER"),
+        postgres_password: source.required("POSTGRES_PASSWORD"),
+        postgres_host: source.parse_or("POSTGRES_HOST", "127.0.0.1".to_string()),
+        postgres_port: source.parse_or("POSTGRES_PORT", 5432),
+        postgres_db: source.required("POSTGRES_DB"),
+        postgres_connection_pool: source.parse_or("POSTGRES_CONNECTION_POOL", 5),
         jwt_keys: JwtKeys::new(jwt_secret.as_bytes()),
         jwt_secret,
-        jwt_expire_access_token_seconds: env_parse("JWT_EXPIRE_ACCESS_TOKEN_SECONDS"),
-        jwt_expire_refresh_token_seconds: env_parse("JWT_EXPIRE_REFRESH_TOKEN_SECONDS"),
-        jwt_validation_leeway_seconds: env_parse("JWT_VALIDATION_LEEWAY_SECONDS"),
-        jwt_enable_revoked_tokens: env_parse("JWT_ENABLE_REVOKED_TOKENS"),
+        jwt_expire_access_token_seconds: source.parse_or("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", 3600),
+        jwt_expire_refresh_token_seconds: source.parse_or(
+            "JWT_EXPIRE_REFRESH_TOKEN_SECONDS",
+            7_776_000
+        ),
+        jwt_validation_leeway_seconds: source.parse_or("JWT_VALIDATION_LEEWAY_SECONDS", 60),
+        jwt_enable_revoked_tokens: source.parse_or("JWT_ENABLE_REVOKED_TOKENS", true),
+        rate_limit_enabled: source.parse_or("RATE_LIMIT_ENABLED", true),
+        rate_limit_store: source.parse_or("RATE_LIMIT_STORE", RateLimitStore::Redis),
+        rate_limit_rules: source.parse_list_or(
+            "RATE_LIMIT_RULES",
+            "/=600/60:ip,/v1/auth/login=10/60:ip,/v1/auth/refresh=30/60:ip,/v1/auth/password=10/60:ip,/v1/auth/email=10/60:ip,/v1/auth/register=10/60:ip"
+        ),
+        rate_limit_api_key_header: source.parse_or(
+            "RATE_LIMIT_API_KEY_HEADER",
+            "x-api-key".to_string()
+        ),
+        rate_limit_redis_timeout_milliseconds: source.parse_or(
+            "RATE_LIMIT_REDIS_TIMEOUT_MILLISECONDS",
+            100
+        ),
+        login_lockout_enabled: source.parse_or("LOGIN_LOCKOUT_ENABLED", true),
+        login_failure_window_seconds: source.parse_or("LOGIN_FAILURE_WINDOW_SECONDS", 900),
+        login_backoff_base_seconds: source.parse_or("LOGIN_BACKOFF_BASE_SECONDS", 1),
+        login_backoff_max_seconds: source.parse_or("LOGIN_BACKOFF_MAX_SECONDS", 60),
+        login_lockout_threshold: source.parse_or("LOGIN_LOCKOUT_THRESHOLD", 10),
+        login_lockout_ip_threshold: source.parse_or("LOGIN_LOCKOUT_IP_THRESHOLD", 100),
+        login_lockout_seconds: source.parse_or("LOGIN_LOCKOUT_SECONDS", 900),
+        password_hash_memory_kib: source.parse_or("PASSWORD_HASH_MEMORY_KIB", 19_456),
+        password_hash_iterations: source.parse_or("PASSWORD_HASH_ITERATIONS", 2),
+        password_hash_parallelism: source.parse_or("PASSWORD_HASH_PARALLELISM", 1),
+        password_pepper: source.optional("PASSWORD_PEPPER"),
+        password_min_length: source.parse_or("PASSWORD_MIN_LENGTH", 12),
+        password_max_length: source.parse_or("PASSWORD_MAX_LENGTH", 128),
+        password_min_character_classes: source.parse_or("PASSWORD_MIN_CHARACTER_CLASSES", 3),
+        password_breached_corpus_path: source.optional("PASSWORD_BREACHED_CORPUS_PATH"),
+        password_reset_token_seconds: source.parse_or("PASSWORD_RESET_TOKEN_SECONDS", 3600),
+        password_reset_url: source.optional("PASSWORD_RESET_URL"),
+        email_verification_required: source.parse_or("EMAIL_VERIFICATION_REQUIRED", false),
+        email_verification_token_seconds: source.parse_or(
+            "EMAIL_VERIFICATION_TOKEN_SECONDS",
+            86_400
+        ),
+        email_verification_url: source.optional("EMAIL_VERIFICATION_URL"),
+        registration_policy: source.parse_or("REGISTRATION_POLICY", RegistrationPolicy::Disabled),
+        registration_allowed_domains: source
+            .list_or("REGISTRATION_ALLOWED_DOMAINS", "")
+            .into_iter()
+            .map(|domain| domain.to_lowercase())
+            .collect(),
+        registration_invite_seconds: source.parse_or("REGISTRATION_INVITE_SECONDS", 604_800),
+        registration_auto_login: source.parse_or("REGISTRATION_AUTO_LOGIN", false),
+        captcha_kind: source.parse_or("CAPTCHA_KIND", CaptchaKind::Disabled),
+        captcha_static_token: source.optional("CAPTCHA_STATIC_TOKEN"),
+        mailer_kind: source.parse_or("MAILER_KIND", MailerKind::Log),
+        mailer_file_dir: source.parse_or("MAILER_FILE_DIR", "mail".to_string()),
+        mailer_from: source.parse_or("MAILER_FROM", "no-reply@localhost".to_string()),
+        log_format: source.parse_or("LOG_FORMAT", LogFormat::Compact),
+        log_filter: source.parse_or("LOG_FILTER", source.default_log_filter()),
+        otel_exporter_otlp_endpoint: source.optional("OTEL_EXPORTER_OTLP_ENDPOINT"),
+        otel_service_name: source.parse_or("OTEL_SERVICE_NAME", SERVICE_NAME.to_string()),
     };
 
-    tracing::trace!("configuration: {:#?}", config);
-    CONFIG.get_or_init(|| config);
+    let mut problems = source.problems;
+    problems.extend(config.validate());
+    if !problems.is_empty() {
+        return Err(ConfigError(problems));
+    }
+    Ok(config)
 }
 
-pub fn get() -> &'static Config {
-    CONFIG.get().unwrap()
-}
+impl Config {
+    fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        let mut check = |valid: bool, problem: &str| {
+            if !valid {
+                problems.push(problem.to_string());
+            }
+        };
+        check(
+            format!("{}:{}", self.service_host, self.service_port).parse::<SocketAddr>().is_ok(),
+            "SERVICE_HOST and SERVICE_PORT must form a valid socket address"
+        );
+        check(
+            self.service_unix_socket_mode.0 <= 0o777,
+            "SERVICE_UNIX_SOCKET_MODE must be a file mode between 000 and 777"
+        );
+        check(
+            self.service_unix_socket.is_none() || !self.tls_enabled(),
+            "TLS is not supported on SERVICE_UNIX_SOCKET"
+        );
+        check(self.redis_port != 0, "REDIS_PORT must not be 0");
+        check(self.postgres_port != 0, "POSTGRES_PORT must not be 0");
+        check(self.postgres_connection_pool > 0, "POSTGRES_CONNECTION_POOL must be positive");
+        check(
+            self.health_check_timeout_milliseconds > 0,
+            "HEALTH_CHECK_TIMEOUT_MILLISECONDS must be positive"
+        );
+        check(
+            self.jwt_secret.len() >= JWT_SECRET_MIN_LENGTH,
+            &format!("JWT_SECRET must be at least {} characters long", JWT_SECRET_MIN_LENGTH)
+        );
+        check(
+            self.jwt_expire_access_token_seconds > 0,
+            "JWT_EXPIRE_ACCESS_TOKEN_SECONDS must be positive"
+        );
+        check(
+            self.jwt_expire_refresh_token_seconds > self.jwt_expire_access_token_seconds,
+            "JWT_EXPIRE_REFRESH_TOKEN_SECONDS must be greater than JWT_EXPIRE_ACCESS_TOKEN_SECONDS"
+        );
+        check(
+            self.jwt_validation_leeway_seconds >= 0,
+            "JWT_VALIDATION_LEEWAY_SECONDS must not be negative"
+        );
+        check(
+            self.tls_cert_path.is_some() == self.tls_key_path.is_some(),
+            "TLS_CERT_PATH and TLS_KEY_PATH must be set together"
+        );
+        check(
+            self.tls_enabled() || self.tls_client_ca_path.is_none(),
+            "TLS_CLIENT_CA_PATH requires TLS_CERT_PATH and TLS_KEY_PATH"
+        );
+        check(
+            self.tls_enabled() || self.tls_redirect_http_port.is_none(),
+            "TLS_REDIRECT_HTTP_PORT requires TLS_CERT_PATH and TLS_KEY_PATH"
+        );
+        check(
+            !matches!(self.tls_redirect_http_port, Some(port) if port == 0 || port == self.service_port),
+            "TLS_REDIRECT_HTTP_PORT must not be 0 or SERVICE_PORT"
+        );
+        for origin in &self.cors_allowed_origins {
+            check(
+                origin == "*" || is_valid_origin(origin),
+                &format!("CORS_ALLOWED_ORIGINS has an invalid origin: {}", origin)
+            );
+        }
+        for method in &self.cors_allowed_methods {
+            check(
+                method.parse::<Method>().is_ok(),
+                &format!("CORS_ALLOWED_METHODS has an invalid method: {}", method)
+            );
+        }
+        for (key, headers) in [
+            ("CORS_ALLOWED_HEADERS", &self.cors_allowed_headers),
+            ("CORS_EXPOSED_HEADERS", &self.cors_exposed_headers),
+        ] {
+            for header in headers {
+                check(
+                    header == "*" || header.parse::<HeaderName>().is_ok(),
+                    &format!("{} has an invalid header: {}", key, header)
+                );
+            }
+        }
+        check(
+            !(self.cors_allow_credentials && self.cors_allowed_origins.iter().any(|o| o == "*")),
+            "CORS_ALLOW_CREDENTIALS cannot be used with any origin (*)"
+        );
+        check(
+            !(
+                self.cors_allow_credentials &&
+                self.cors_allowed_headers
+                    .iter()
+                    .chain(&self.cors_exposed_headers)
+                    .any(|h| h == "*")
+            ),
+            "CORS_ALLOW_CREDENTIALS cannot be used with any header (*)"
+        );
+        for route in &self.cors_public_routes {
+            check(
+                route.starts_with('/'),
+                &format!("CORS_PUBLIC_ROUTES has an invalid route: {}", route)
+            );
+        }
+        check(
+            self.rate_limit_api_key_header.parse::<HeaderName>().is_ok(),
+            &format!("RATE_LIMIT_API_KEY_HEADER has an invalid header: {}", self.rate_limit_api_key_header)
+        );
+        check(
+            self.rate_limit_redis_timeout_milliseconds > 0,
+            "RATE_LIMIT_REDIS_TIMEOUT_MILLISECONDS must be positive"
+        );
+        check(
+            self.login_failure_window_seconds > 0,
+            "LOGIN_FAILURE_WINDOW_SECONDS must be positive"
+        );
+        check(
+            self.login_backoff_max_seconds >= self.login_backoff_base_seconds,
+            "LOGIN_BACKOFF_MAX_SECONDS must not be less than LOGIN_BACKOFF_BASE_SECONDS"
+        );
+        check(
+            self.login_lockout_threshold > 0 && self.login_lockout_ip_threshold > 0,
+            "LOGIN_LOCKOUT_THRESHOLD and LOGIN_LOCKOUT_IP_THRESHOLD must be positive"
+        );
+        check(self.login_lockout_seconds > 0, "LOGIN_LOCKOUT_SECONDS must be positive");
+        check(
+            argon2::Params
+                ::new(
+                    self.password_hash_memory_kib,
+                    self.password_hash_iterations,
+                    self.password_hash_parallelism,
+                    None
+                )
+                .is_ok(),
+            "PASSWORD_HASH_MEMORY_KIB, PASSWORD_HASH_ITERATIONS and PASSWORD_HASH_PARALLELISM must be valid Argon2 parameters"
+        );
+        check(
+            self.password_pepper.as_ref().is_none_or(|pepper| !pepper.is_empty()),
+            "PASSWORD_PEPPER must not be empty"
+        );
+        check(self.password_min_length > 0, "PASSWORD_MIN_LENGTH must be positive");
+        check(
+            self.password_max_length >= self.password_min_length,
+            "PASSWORD_MAX_LENGTH must not be less than PASSWORD_MIN_LENGTH"
+        );
+        check(
+            self.password_min_character_classes <= 4,
+            "PASSWORD_MIN_CHARACTER_CLASSES must be between 0 and 4"
+        );
+        check(
+            self.password_reset_token_seconds > 0,
+            "PASSWORD_RESET_TOKEN_SECONDS must be positive"
+        );
+        check(
+            self.password_reset_url.as_ref().is_none_or(|url| !url.is_empty()),
+            "PASSWORD_RESET_URL must not be empty"
+        );
+        check(
+            self.email_verification_token_seconds > 0,
+            "EMAIL_VERIFICATION_TOKEN_SECONDS must be positive"
+        );
+        check(
+            self.email_verification_url.as_ref().is_none_or(|url| !url.is_empty()),
+            "EMAIL_VERIFICATION_URL must not be empty"
+        );
+        check(
+            self.registration_policy != RegistrationPolicy::DomainAllowlist ||
+                !self.registration_allowed_domains.is_empty(),
+            "REGISTRATION_ALLOWED_DOMAINS is required by the domain_allowlist registration policy"
+        );
+        check(self.registration_invite_seconds > 0, "REGISTRATION_INVITE_SECONDS must be positive");
+        check(
+            self.captcha_kind != CaptchaKind::Static ||
+                self.captcha_static_token.as_ref().is_some_and(|token| !token.is_empty()),
+            "CAPTCHA_STATIC_TOKEN is required by the static captcha"
+        );
+        check(
+            self.mailer_kind != MailerKind::File || !self.mailer_file_dir.is_empty(),
+            "MAILER_FILE_DIR is required by the file mailer"
+        );
+        check(!self.mailer_from.is_empty(), "MAILER_FROM must not be empty");
+        check(
+            EnvFilter::try_new(&self.log_filter).is_ok(),
+            &format!("LOG_FILTER is not a valid filter: {}", self.log_filter)
+        );
+        problems
+    }
 
-#[inline]
-fn env_get(key: &str) -> String {
-    match std::env::var(key) {
-        Ok(v) => v,
-        Err(e) => {
-            let msg = format!("{} {}", key, e);
-            tracing::error!(msg);
-            panic!("{msg}");
+    /// Applies the reloadable settings of the `new` configuration to a copy of this one,
+    /// the changes of the other settings are reported but take effect only after a restart
+    pub fn reload(&self, new: &Config) -> ConfigReload {
+        let mut config = self.clone();
+        let mut changed = Vec::new();
+        let mut ignored = Vec::new();
+
+        macro_rules! reloadable {
+            ($($field:ident),+) => {
+                $(
+                    if config.$field != new.$field {
+                        changed.push(
+                            format!("{}: {:?} -> {:?}", stringify!($field), config.$field, new.$field)
+                        );
+                        config.$field = new.$field.clone();
+                    }
+                )+
+            };
+        }
+        macro_rules! restart_required {
+            ($($field:ident),+) => {
+                $(
+                    if config.$field != new.$field {
+                        ignored.push(stringify!($field).to_string());
+                    }
+                )+
+            };
         }
+
+        // every new setting has to be listed in one of the groups
+        reloadable!(
+            jwt_expire_access_token_seconds,
+            jwt_expire_refresh_token_seconds,
+            jwt_validation_leeway_seconds,
+            log_filter,
+            cors_allowed_origins,
+            cors_allowed_methods,
+            cors_allowed_headers,
+            cors_exposed_headers,
+            cors_allow_credentials,
+            cors_max_age_seconds,
+            cors_public_routes,
+            rate_limit_enabled,
+            rate_limit_store,
+            rate_limit_rules,
+            rate_limit_api_key_header,
+            rate_limit_redis_timeout_milliseconds,
+            service_trust_forwarded_for,
+            login_lockout_enabled,
+            login_failure_window_seconds,
+            login_backoff_base_seconds,
+            login_backoff_max_seconds,
+            login_lockout_threshold,
+            login_lockout_ip_threshold,
+            login_lockout_seconds,
+            password_hash_memory_kib,
+            password_hash_iterations,
+            password_hash_parallelism,
+            password_min_length,
+            password_max_length,
+            password_min_character_classes,
+            password_reset_token_seconds,
+            password_reset_url,
+            email_verification_required,
+            email_verification_token_seconds,
+            email_verification_url,
+            registration_policy,
+            registration_allowed_domains,
+            registration_invite_seconds,
+            registration_auto_login,
+            mailer_from
+        );
+        restart_required!(
+            config_watch_interval_seconds,
+            service_host,
+            service_port,
+            service_unix_socket,
+            service_unix_socket_mode,
+            api_version_negotiation,
+            health_check_timeout_milliseconds,
+            shutdown_pre_stop_delay_seconds,
+            shutdown_drain_timeout_seconds,
+            tls_cert_path,
+            tls_key_path,
+            tls_client_ca_path,
+            tls_client_auth_required,
+            tls_reload_interval_seconds,
+            tls_redirect_http_port,
+            redis_host,
+            redis_port,
+            postgres_user,
+            postgres_password,
+            postgres_host,
+            postgres_port,
+            postgres_db,
+            postgres_connection_pool,
+            jwt_secret,
+            password_pepper,
+            password_breached_corpus_path,
+            mailer_kind,
+            mailer_file_dir,
+            captcha_kind,
+            captcha_static_token,
+            jwt_enable_revoked_tokens,
+            log_format,
+            otel_exporter_otlp_endpoint,
+            otel_service_name
+        );
+
+        ConfigReload { config, changed, ignored }
     }
 }
 
-#[inline]
-fn env_get_or(key: &str, value: &str) -> String {
-    if let Ok(v) = std::env::var(key) {
-        return v;
+/// The outcome of a configuration reload
+#[derive(Debug)]
+pub struct ConfigReload {
+    pub config: Config,
+    /// the applied changes as `setting: old -> new`
+    pub changed: Vec<String>,
+    /// the changed settings which require a restart, their values are not reported
+    pub ignored: Vec<String>,
+}
+
+/// All the problems found while loading the configuration
+#[derive(Debug, thiserror::Error)]
+#[error("invalid configuration:\n  - {}", .0.join("\n  - "))]
+pub struct ConfigError(pub Vec<String>);
+
+// resolves the configuration keys through the layers and collects the problems
+struct ConfigSource {
+    file: HashMap<String, String>,
+    // whether the environment overrides the file
+    environment: bool,
+    problems: Vec<String>,
+}
+
+impl ConfigSource {
+    fn new(contents: &str, environment: bool) -> Self {
+        let mut source = Self {
+            file: HashMap::new(),
+            environment,
+            problems: Vec::new(),
+        };
+        match contents.parse::<toml::Table>() {
+            Ok(table) => flatten_table("", &table, &mut source.file),
+            Err(e) => source.problems.push(format!("TOML: {}", e.message())),
+        }
+        source
+    }
+
+    fn value(&mut self, key: &str) -> Option<String> {
+        if !self.environment {
+            return self.file.get(key).cloned();
+        }
+        if let Some(value) = std::env::var(key).ok().filter(|v| !v.is_empty()) {
+            return Some(value);
+        }
+        let file_key = format!("{}_FILE", key);
+        if let Some(path) = std::env::var(&file_key).ok().filter(|v| !v.is_empty()) {
+            return match std::fs::read_to_string(&path) {
+                Ok(value) => Some(value.trim_end_matches(['\r', '\n']).to_string()),
+                Err(e) => {
+                    self.problems.push(format!("{} {}: {}", file_key, path, e));
+                    None
+                }
+            };
+        }
+        self.file.get(key).cloned()
+    }
+
+    fn default_log_filter(&self) -> String {
+        std::env::var("RUST_LOG")
+            .ok()
+            .filter(|_| self.environment)
+            .unwrap_or_else(|| "axum_web=trace".to_string())
     }
-    value.to_string()
+
+    fn optional(&mut self, key: &str) -> Option<String> {
+        self.value(key)
+    }
+
+    fn required<T: FromStr + Default>(&mut self, key: &str) -> T {
+        match self.value(key) {
+            Some(value) => self.parse(key, &value).unwrap_or_default(),
+            None => {
+                self.problems.push(format!("{} is missing", key));
+                T::default()
+            }
+        }
+    }
+
+    // a comma separated list or a TOML array
+    fn list_or(&mut self, key: &str, default: &str) -> Vec<String> {
+        self.value(key)
+            .as_deref()
+            .unwrap_or(default)
+            .split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    }
+
+    // a comma separated list or a TOML array of values, each one parsed
+    fn parse_list_or<T: FromStr>(&mut self, key: &str, default: &str) -> Vec<T> {
+        let items = self.list_or(key, default);
+        items
+            .iter()
+            .filter_map(|item| self.parse(key, item))
+            .collect()
+    }
+
+    fn parse_optional<T: FromStr>(&mut self, key: &str) -> Option<T> {
+        let value = self.value(key)?;
+        self.parse(key, &value)
+    }
+
+    fn parse_or<T: FromStr>(&mut self, key: &str, default: T) -> T {
+        match self.value(key) {
+            Some(value) => self.parse(key, &value).unwrap_or(default),
+            None => default,
+        }
+    }
+
+    fn parse<T: FromStr>(&mut self, key: &str, value: &str) -> Option<T> {
+        let parsed = value.trim().parse().ok();
+        if parsed.is_none() {
+            self.problems.push(format!("{} has an invalid value: {}", key, value));
+        }
+        parsed
+    }
+}
+
+// `scheme://host[:port]`, the host may start with a `*.` wildcard for any subdomain
+fn is_valid_origin(origin: &str) -> bool {
+    let Some((scheme, host)) = origin.split_once("://") else {
+        return false;
+    };
+    let host = host.strip_prefix("*.").unwrap_or(host);
+    !scheme.is_empty() &&
+        !host.is_empty() &&
+        !host.contains(['/', '*']) &&
+        format!("{}://{}", scheme, host).parse::<HeaderValue>().is_ok()
 }
 
-#[inline]
-fn env_parse<T: std::str::FromStr>(key: &str) -> T {
-    match env_get(key).parse() {
-        Ok(v) => v,
-        Err(_) => {
-            let msg = format!("Failed to parse: {}", key);
-            tracing::error!(msg);
-            panic!("{msg}");
+// `[jwt] expire_access_token_seconds = 60` is read as `JWT_EXPIRE_ACCESS_TOKEN_SECONDS`
+fn flatten_table(prefix: &str, table: &toml::Table, keys: &mut HashMap<String, String>) {
+    for (name, value) in table {
+        let key = if prefix.is_empty() {
+            name.to_uppercase()
+        } else {
+            format!("{}_{}", prefix, name.to_uppercase())
+        };
+        match value {
+            toml::Value::Table(table) => flatten_table(&key, table, keys),
+            toml::Value::String(value) => {
+                keys.insert(key, value.clone());
+            }
+            toml::Value::Array(values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|value| {
+                        match value {
+                            toml::Value::String(value) => value.clone(),
+                            value => value.to_string(),
+                        }
+                    })
+                    .collect();
+                keys.insert(key, values.join(","));
+            }
+            value => {
+                keys.insert(key, value.to_string());
+            }
         }
     }
 }
@@ -0,0 +1,93 @@
+use std::{ sync::Arc, time::{ Duration, SystemTime } };
+
+use crate::infrastructure::telemetry;
+
+use super::{ config::{ self, ConfigError }, state::SharedState };
+
+/// Reloads the configuration and swaps the reloadable settings of the application state.
+/// An invalid configuration is rejected and the current one is kept.
+pub fn reload(state: &SharedState) -> Result<Vec<String>, ConfigError> {
+    let new_config = config::try_load()?;
+    let previous = state.config();
+    let reload = previous.reload(&new_config);
+
+    if reload.changed.is_empty() {
+        tracing::info!("configuration reloaded, no changes");
+    } else {
+        if let Err(e) = telemetry::reload_filter(&reload.config.log_filter) {
+            return Err(ConfigError(vec![format!("LOG_FILTER could not be applied: {}", e)]));
+        }
+        let config = Arc::new(reload.config);
+        state.set_config(config.clone());
+        config::swap_shared(&previous, config);
+        tracing::info!("configuration reloaded, changes: {}", reload.changed.join(", "));
+    }
+    if !reload.ignored.is_empty() {
+        tracing::warn!(
+            "configuration changes which require a restart are ignored: {}",
+            reload.ignored.join(", ")
+        );
+    }
+    let shadowed = config::shadowed_keys();
+    if !shadowed.is_empty() {
+        tracing::warn!(
+            "{} keys overridden by the environment are not reloaded: {}",
+            config::config_file(),
+            shadowed.join(", ")
+        );
+    }
+    Ok(reload.changed)
+}
+
+/// Reloads the configuration on SIGHUP or when the configuration file is modified
+pub async fn watch(state: SharedState) {
+    // polling the modification time, 0 disables the file watching
+    let watch_interval = state.config().config_watch_interval_seconds;
+    let mut interval = (watch_interval > 0).then(|| {
+        tokio::time::interval(Duration::from_secs(watch_interval))
+    });
+    let mut modified = config_file_modified();
+
+    #[cfg(unix)]
+    let mut hangup = tokio::signal::unix
+        ::signal(tokio::signal::unix::SignalKind::hangup())
+        .expect("failed to install SIGHUP handler");
+
+    loop {
+        #[cfg(unix)]
+        let hangup = hangup.recv();
+        #[cfg(not(unix))]
+        let hangup = std::future::pending::<Option<()>>();
+
+        let tick = async {
+            match interval.as_mut() {
+                Some(interval) => {
+                    interval.tick().await;
+                }
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            _ = hangup => {
+                tracing::info!("received SIGHUP, reloading configuration");
+            }
+            _ = tick => {
+                let current = config_file_modified();
+                if current == modified {
+                    continue;
+                }
+                modified = current;
+                tracing::info!("{} modified, reloading configuration", config::config_file());
+            }
+        }
+
+        if let Err(e) = reload(&state) {
+            tracing::error!("configuration reload rejected, {}", e);
+        }
+    }
+}
+
+fn config_file_modified() -> Option<SystemTime> {
+    std::fs::metadata(config::config_file()).and_then(|metadata| metadata.modified()).ok()
+}
@@ -0,0 +1,80 @@
+use chrono::Utc;
+
+use crate::{ domain::models::user::User, infrastructure::mailer::Mail };
+
+use super::{
+    api_error::ApiError,
+    api_validation::FieldError,
+    audit_service::{ self, AuditEvent },
+    repository::{ email_verification_repo, user_repo },
+    security::one_time_token,
+    state::SharedState,
+};
+
+// A verification token is sent when a user is created and when the email changes,
+// it verifies only the email it was sent to and expires after `EMAIL_VERIFICATION_TOKEN_SECONDS`.
+// With `EMAIL_VERIFICATION_REQUIRED` the users cannot log in until their email is verified.
+
+/// Sends a verification token to the email of the user, unless it is verified already
+pub async fn send_verification(user: User, state: SharedState) {
+    if user.email_verified_at.is_some() {
+        return;
+    }
+
+    let config = state.config();
+    let token = one_time_token::generate();
+    let expires_at =
+        Utc::now() + chrono::Duration::seconds(config.email_verification_token_seconds as i64);
+    let added = email_verification_repo::add_token(
+        user.id,
+        &user.email,
+        &one_time_token::hash(&token),
+        expires_at.naive_utc(),
+        &state
+    ).await;
+    if !added {
+        return;
+    }
+
+    let link = match &config.email_verification_url {
+        Some(url) => format!("{}{}", url, token),
+        None => token,
+    };
+    let mail = Mail {
+        from: config.mailer_from.clone(),
+        to: user.email,
+        subject: "Verify your email".to_string(),
+        body: format!(
+            "Verify the email of your account {} with the following token within {} hours:\n\n\
+             {}\n\n\
+             If you do not have an account, ignore this message.\n",
+            user.username,
+            config.email_verification_token_seconds.div_ceil(3600),
+            link
+        ),
+    };
+    if let Err(e) = state.mailer.send(&mail).await {
+        tracing::error!("email verification mail not sent: {}", e);
+        return;
+    }
+    audit_service::record(AuditEvent::EmailVerificationSent { user_id: &user.id.to_string() });
+}
+
+/// Sends a new token to an active user with the unverified email, the other emails are ignored
+pub async fn resend(email: String, state: SharedState) {
+    match user_repo::get_user_by_email(&email, &state).await {
+        Some(user) if user.active => send_verification(user, state).await,
+        _ => tracing::debug!("email verification of an unknown email"),
+    }
+}
+
+/// Marks the email the token was sent to as verified
+pub async fn verify(token: &str, state: &SharedState) -> Result<(), ApiError> {
+    match email_verification_repo::verify(&one_time_token::hash(token), state).await {
+        Some(user_id) => {
+            audit_service::record(AuditEvent::EmailVerified { user_id: &user_id.to_string() });
+            Ok(())
+        }
+        None => Err(vec![FieldError::new("token", "invalid", "is invalid, expired or already used")].into()),
+    }
+}
@@ -0,0 +1,103 @@
+use serde::Serialize;
+use std::time::{ Duration, Instant };
+use tokio::time::timeout;
+use utoipa::ToSchema;
+
+use super::state::SharedState;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PoolStatus {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+    /// share of the maximum connections currently in use
+    pub saturation: f64,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct DependencyHealth {
+    pub status: HealthStatus,
+    pub latency_ms: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pool: Option<PoolStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Readiness {
+    pub ready: bool,
+    /// the service is shutting down and should not receive new requests
+    pub shutting_down: bool,
+    pub postgres: DependencyHealth,
+    pub redis: DependencyHealth,
+}
+
+pub async fn readiness(state: &SharedState) -> Readiness {
+    let check_timeout = Duration::from_millis(state.config().health_check_timeout_milliseconds);
+    let (postgres, redis) = tokio::join!(
+        check_postgres(state, check_timeout),
+        check_redis(state, check_timeout)
+    );
+    let shutting_down = state.shutdown.is_triggered();
+    Readiness {
+        ready: !shutting_down && postgres.status == HealthStatus::Up && redis.status == HealthStatus::Up,
+        shutting_down,
+        postgres,
+        redis,
+    }
+}
+
+async fn check_postgres(state: &SharedState, check_timeout: Duration) -> DependencyHealth {
+    let started = Instant::now();
+    let result = timeout(check_timeout, sqlx::query("SELECT 1").execute(&state.pgpool)).await;
+    let mut health = dependency_health("postgres", started, result);
+
+    let pgpool = &state.pgpool;
+    let size = pgpool.size();
+    let idle = pgpool.num_idle();
+    let max = pgpool.options().get_max_connections();
+    health.pool = Some(PoolStatus {
+        size,
+        idle,
+        max,
+        saturation: (size as f64 - idle as f64).max(0.0) / max as f64,
+    });
+    health
+}
+
+async fn check_redis(state: &SharedState, check_timeout: Duration) -> DependencyHealth {
+    let started = Instant::now();
+    let result = timeout(check_timeout, async {
+        let mut redis = state.redis.lock().await;
+        redis::cmd("PING").query_async::<_, String>(&mut *redis).await
+    }).await;
+    dependency_health("redis", started, result)
+}
+
+// the probes are not authenticated, the failure details only go to the log
+fn dependency_health<T, E: std::fmt::Display>(
+    dependency: &str,
+    started: Instant,
+    result: Result<Result<T, E>, tokio::time::error::Elapsed>
+) -> DependencyHealth {
+    let latency_ms = started.elapsed().as_secs_f64() * 1000.0;
+    let error = match result {
+        Ok(Ok(_)) => None,
+        Ok(Err(e)) => Some(e.to_string()),
+        Err(_) => Some("timed out".to_string()),
+    };
+    if let Some(e) = &error {
+        tracing::error!("health check of {} failed: {}", dependency, e);
+    }
+    DependencyHealth {
+        status: if error.is_none() { HealthStatus::Up } else { HealthStatus::Down },
+        latency_ms,
+        pool: None,
+    }
+}
@@ -0,0 +1,165 @@
+use redis::{ AsyncCommands, RedisResult };
+use std::net::IpAddr;
+
+use super::{
+    app_const::{ LOGIN_BLOCKED_REDIS_KEY_PREFIX, LOGIN_FAILURES_REDIS_KEY_PREFIX },
+    audit_service::{ self, AuditEvent },
+    config::Config,
+    metrics_service,
+    state::SharedState,
+};
+
+// The failures are counted by username and by ip, whether the username exists or not,
+// so the responses do not tell the existing usernames apart.
+// A username is blocked for an exponential backoff after each failure and locked after
+// `LOGIN_LOCKOUT_THRESHOLD` failures, an ip is only locked after `LOGIN_LOCKOUT_IP_THRESHOLD` failures.
+// The counters are kept in Redis, the login is not limited when Redis fails.
+
+/// Whole seconds until the username and the ip may log in again, `None` if they are not blocked
+pub async fn blocked(state: &SharedState, username: &str, ip: Option<IpAddr>) -> Option<u64> {
+    let config = state.config();
+    if !config.login_lockout_enabled {
+        return None;
+    }
+    match blocked_redis(state, &subjects(username, ip)).await {
+        Ok(pttl) => pttl.map(|pttl| pttl.div_ceil(1000)),
+        Err(e) => {
+            tracing::error!("login lockout check failed: {}", e);
+            metrics_service::record_redis_error("login_lockout");
+            None
+        }
+    }
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "lockout_service::blocked_redis",
+    skip_all,
+    fields(db.system = "redis", db.operation = "PTTL")
+)]
+async fn blocked_redis(state: &SharedState, subjects: &[String]) -> RedisResult<Option<u64>> {
+    let mut pipe = redis::pipe();
+    for subject in subjects {
+        pipe.pttl(blocked_key(subject));
+    }
+    let mut redis = state.redis.lock().await;
+    // a missing key has a negative ttl
+    let pttls: Vec<i64> = pipe.query_async(&mut *redis).await?;
+    Ok(pttls.into_iter().max().filter(|pttl| *pttl > 0).map(|pttl| pttl as u64))
+}
+
+/// Counts a failed login of the username from the ip and blocks them when due
+pub async fn record_failure(state: &SharedState, username: &str, ip: Option<IpAddr>) {
+    let config = state.config();
+    if !config.login_lockout_enabled {
+        return;
+    }
+    for subject in subjects(username, ip) {
+        if let Err(e) = record_failure_redis(state, &config, &subject).await {
+            tracing::error!("login failure not counted: {}", e);
+            metrics_service::record_redis_error("login_lockout");
+        }
+    }
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "lockout_service::record_failure_redis",
+    skip_all,
+    fields(db.system = "redis", db.operation = "INCR EXPIRE SET")
+)]
+async fn record_failure_redis(state: &SharedState, config: &Config, subject: &str) -> RedisResult<()> {
+    let failures_key = failures_key(subject);
+    let mut redis = state.redis.lock().await;
+    let (failures,): (u64,) = redis::pipe()
+        .incr(&failures_key, 1)
+        .expire(&failures_key, config.login_failure_window_seconds as i64)
+        .ignore()
+        .query_async(&mut *redis).await?;
+
+    let is_ip = subject.starts_with("ip:");
+    let threshold = if is_ip { config.login_lockout_ip_threshold } else { config.login_lockout_threshold };
+    let block_seconds = if failures >= threshold {
+        audit_service::record(AuditEvent::LoginLocked {
+            subject,
+            failures,
+            lockout_seconds: config.login_lockout_seconds,
+        });
+        metrics_service::record_login_lockout(if is_ip { "ip" } else { "username" });
+        config.login_lockout_seconds
+    } else if is_ip {
+        0
+    } else {
+        backoff_seconds(config, failures)
+    };
+    if block_seconds > 0 {
+        redis.set_ex::<_, _, ()>(blocked_key(subject), 1, block_seconds).await?;
+    }
+    Ok(())
+}
+
+// base * 2^(failures - 1), capped at the maximum
+fn backoff_seconds(config: &Config, failures: u64) -> u64 {
+    let exponent = failures.saturating_sub(1).min(63) as u32;
+    config.login_backoff_base_seconds
+        .saturating_mul(1u64 << exponent)
+        .min(config.login_backoff_max_seconds)
+}
+
+/// Forgets the failures of the username after a successful login,
+/// the failures of the ip are kept, so a known account cannot reset them
+pub async fn record_success(state: &SharedState, username: &str) {
+    if !state.config().login_lockout_enabled {
+        return;
+    }
+    let subject = user_subject(username);
+    let mut redis = state.redis.lock().await;
+    if let Err(e) = redis.del::<_, ()>(failures_key(&subject)).await {
+        tracing::error!("login failures not cleared: {}", e);
+        metrics_service::record_redis_error("login_lockout");
+    }
+}
+
+/// Unlocks the username or the ip and forgets their failures, false if Redis fails
+pub async fn unlock(state: &SharedState, username: Option<&str>, ip: Option<IpAddr>, admin_id: &str) -> bool {
+    let subjects: Vec<String> = username.map(user_subject).into_iter().chain(ip.map(ip_subject)).collect();
+    let keys: Vec<String> = subjects
+        .iter()
+        .flat_map(|subject| [failures_key(subject), blocked_key(subject)])
+        .collect();
+    if keys.is_empty() {
+        return true;
+    }
+
+    let mut redis = state.redis.lock().await;
+    if let Err(e) = redis.del::<_, ()>(keys).await {
+        tracing::error!("login not unlocked: {}", e);
+        metrics_service::record_redis_error("login_lockout");
+        return false;
+    }
+    for subject in &subjects {
+        audit_service::record(AuditEvent::LoginUnlocked { subject, admin_id });
+    }
+    true
+}
+
+fn subjects(username: &str, ip: Option<IpAddr>) -> Vec<String> {
+    std::iter::once(user_subject(username)).chain(ip.map(ip_subject)).collect()
+}
+
+// the usernames differing only by case share the counters
+fn user_subject(username: &str) -> String {
+    format!("user:{}", username.to_lowercase())
+}
+
+fn ip_subject(ip: IpAddr) -> String {
+    format!("ip:{}", ip)
+}
+
+fn failures_key(subject: &str) -> String {
+    format!("{}.{}", LOGIN_FAILURES_REDIS_KEY_PREFIX, subject)
+}
+
+fn blocked_key(subject: &str) -> String {
+    format!("{}.{}", LOGIN_BLOCKED_REDIS_KEY_PREFIX, subject)
+}
@@ -0,0 +1,97 @@
+use metrics::{ counter, gauge, histogram };
+use metrics_exporter_prometheus::{ Matcher, PrometheusBuilder, PrometheusHandle };
+use std::{ sync::OnceLock, time::Duration };
+
+use super::state::SharedState;
+
+static PROMETHEUS: OnceLock<PrometheusHandle> = OnceLock::new();
+
+// latency buckets in seconds, used for all `*_duration_seconds` histograms
+const DURATION_BUCKETS: &[f64] = &[
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// HTTP metrics
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUESTS_DURATION_SECONDS: &str = "http_requests_duration_seconds";
+
+// domain metrics
+pub const AUTH_LOGINS_TOTAL: &str = "auth_logins_total";
+pub const AUTH_LOGIN_LOCKOUTS_TOTAL: &str = "auth_login_lockouts_total";
+pub const AUTH_TOKENS_ISSUED_TOTAL: &str = "auth_tokens_issued_total";
+pub const AUTH_TOKENS_REVOKED_TOTAL: &str = "auth_tokens_revoked_total";
+pub const AUTH_REVOCATION_CHECK_DURATION_SECONDS: &str = "auth_revocation_check_duration_seconds";
+pub const POSTGRES_POOL_CONNECTIONS: &str = "postgres_pool_connections";
+pub const REDIS_ERRORS_TOTAL: &str = "redis_errors_total";
+pub const HTTP_REQUESTS_RATE_LIMITED_TOTAL: &str = "http_requests_rate_limited_total";
+
+/// Installs the global Prometheus recorder.
+/// The recorder is process wide, repeated calls keep the first installed one.
+pub fn install() -> &'static PrometheusHandle {
+    PROMETHEUS.get_or_init(|| {
+        PrometheusBuilder::new()
+            .set_buckets_for_metric(Matcher::Suffix("duration_seconds".to_string()), DURATION_BUCKETS)
+            .expect("Could not set histogram buckets")
+            .install_recorder()
+            .expect("Could not install the Prometheus recorder")
+    })
+}
+
+/// Renders the metrics in the Prometheus text exposition format
+pub fn render(state: &SharedState) -> String {
+    record_pool_usage(state);
+    install().render()
+}
+
+pub fn record_request(method: &str, route: &str, status: u16, duration: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("route", route.to_string()),
+        ("status", status.to_string()),
+    ];
+    counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    histogram!(HTTP_REQUESTS_DURATION_SECONDS, &labels).record(duration.as_secs_f64());
+}
+
+// route: the path of the rate limit rule
+pub fn record_rate_limited(route: &str) {
+    counter!(HTTP_REQUESTS_RATE_LIMITED_TOTAL, "route" => route.to_string()).increment(1);
+}
+
+pub fn record_login(succeeded: bool) {
+    let result = if succeeded { "success" } else { "failure" };
+    counter!(AUTH_LOGINS_TOTAL, "result" => result).increment(1);
+}
+
+// scope: `username` or `ip`
+pub fn record_login_lockout(scope: &'static str) {
+    counter!(AUTH_LOGIN_LOCKOUTS_TOTAL, "scope" => scope).increment(1);
+}
+
+pub fn record_tokens_issued(count: u64) {
+    counter!(AUTH_TOKENS_ISSUED_TOTAL).increment(count);
+}
+
+// scope: `token`, `user` or `global`
+pub fn record_tokens_revoked(scope: &'static str, count: u64) {
+    counter!(AUTH_TOKENS_REVOKED_TOTAL, "scope" => scope).increment(count);
+}
+
+pub fn record_revocation_check(duration: Duration) {
+    histogram!(AUTH_REVOCATION_CHECK_DURATION_SECONDS).record(duration.as_secs_f64());
+}
+
+pub fn record_redis_error(operation: &'static str) {
+    counter!(REDIS_ERRORS_TOTAL, "operation" => operation).increment(1);
+}
+
+fn record_pool_usage(state: &SharedState) {
+    let pgpool = &state.pgpool;
+    let size = pgpool.size() as f64;
+    let idle = pgpool.num_idle() as f64;
+    gauge!(POSTGRES_POOL_CONNECTIONS, "state" => "active").set(size - idle);
+    gauge!(POSTGRES_POOL_CONNECTIONS, "state" => "idle").set(idle);
+    gauge!(POSTGRES_POOL_CONNECTIONS, "state" => "max").set(
+        pgpool.options().get_max_connections() as f64
+    );
+}
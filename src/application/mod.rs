@@ -1,10 +1,24 @@
+pub mod api_client_ip;
 pub mod api_error;
 pub mod api_json;
 pub mod api_path;
+pub mod api_problem;
+pub mod api_query;
+pub mod api_validation;
 pub mod app;
 pub mod app_const;
+pub mod audit_service;
 pub mod config;
+pub mod config_service;
+pub mod email_verification_service;
+pub mod health_service;
+pub mod lockout_service;
+pub mod metrics_service;
+pub mod password_reset_service;
+pub mod rate_limit_service;
 pub mod redis_service;
+pub mod registration_service;
 pub mod repository;
 pub mod security;
+pub mod shutdown;
 pub mod state;
@@ -0,0 +1,109 @@
+use chrono::Utc;
+use hyper::StatusCode;
+
+use crate::infrastructure::mailer::Mail;
+
+use super::{
+    api_error::ApiError,
+    api_validation::FieldError,
+    audit_service::{ self, AuditEvent },
+    lockout_service,
+    redis_service,
+    repository::{ password_reset_repo, user_repo },
+    security::{ one_time_token, password, password_policy },
+    state::SharedState,
+};
+
+// The reset tokens are random, single-use and expire after `PASSWORD_RESET_TOKEN_SECONDS`,
+// only their SHA-256 hashes are stored. The request is answered before the user is looked up,
+// so the responses do not tell the registered emails apart.
+
+/// Sends a reset token to the email of an active user, the unknown emails are ignored
+pub async fn request_reset(email: String, state: SharedState) {
+    let Some(user) = user_repo::get_user_by_email(&email, &state).await else {
+        tracing::debug!("password reset of an unknown email");
+        return;
+    };
+    if !user.active {
+        tracing::debug!("password reset of an inactive user: {}", user.id);
+        return;
+    }
+
+    let config = state.config();
+    let token = one_time_token::generate();
+    let expires_at =
+        Utc::now() + chrono::Duration::seconds(config.password_reset_token_seconds as i64);
+    if !password_reset_repo::add_token(user.id, &one_time_token::hash(&token), expires_at.naive_utc(), &state).await {
+        return;
+    }
+
+    let link = match &config.password_reset_url {
+        Some(url) => format!("{}{}", url, token),
+        None => token,
+    };
+    let mail = Mail {
+        from: config.mailer_from.clone(),
+        to: user.email,
+        subject: "Reset your password".to_string(),
+        body: format!(
+            "A password reset was requested for your account {}.\n\n\
+             Reset your password with the following token within {} minutes, it can be used once:\n\n\
+             {}\n\n\
+             If you did not request it, ignore this message.\n",
+            user.username,
+            config.password_reset_token_seconds.div_ceil(60),
+            link
+        ),
+    };
+    if let Err(e) = state.mailer.send(&mail).await {
+        tracing::error!("password reset mail not sent: {}", e);
+        return;
+    }
+    audit_service::record(AuditEvent::PasswordResetRequested { user_id: &user.id.to_string() });
+}
+
+/// Sets the new password of the user of the token and revokes the tokens issued to the user
+pub async fn reset(token: &str, new_password: &str, state: &SharedState) -> Result<(), ApiError> {
+    let token_hash = one_time_token::hash(token);
+    let user = match password_reset_repo::get_user_id(&token_hash, state).await {
+        Some(user_id) => user_repo::get_user(user_id, state).await.filter(|user| user.active),
+        None => None,
+    };
+    let Some(user) = user else {
+        return Err(invalid_token());
+    };
+
+    let config = state.config();
+    let errors = password_policy::validate(
+        "new_password",
+        new_password,
+        &user.username,
+        &user.email,
+        &config,
+        &state.breached_passwords
+    );
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    // used up only now, a rejected password keeps the token valid
+    if password_reset_repo::use_token(&token_hash, state).await != Some(user.id) {
+        return Err(invalid_token());
+    }
+    let new_hash = password::hash_password(new_password.as_bytes(), &config);
+    if !user_repo::update_password(user.id, &new_hash, state).await {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    let user_id = user.id.to_string();
+    if !redis_service::revoke_user_tokens(&user_id, state).await {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    // the owner of the account is back in, the failed logins are forgotten
+    lockout_service::record_success(state, &user.username).await;
+    audit_service::record(AuditEvent::PasswordReset { user_id: &user_id });
+    Ok(())
+}
+
+fn invalid_token() -> ApiError {
+    vec![FieldError::new("token", "invalid", "is invalid, expired or already used")].into()
+}
@@ -0,0 +1,183 @@
+use redis::{ AsyncCommands, RedisResult };
+use std::{ collections::HashMap, sync::Mutex, time::{ Duration, SystemTime, UNIX_EPOCH } };
+use tokio::time::timeout;
+
+use super::{
+    app_const::RATE_LIMIT_REDIS_KEY_PREFIX,
+    config::{ RateLimitRule, RateLimitStore },
+    metrics_service,
+    state::SharedState,
+};
+
+// the in-memory counters are pruned when there are more clients
+const MEMORY_MAX_CLIENTS: usize = 10_000;
+
+/// The outcome of counting a request against a rate limit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimitDecision {
+    pub allowed: bool,
+    pub limit: u64,
+    pub remaining: u64,
+    /// time until the current window ends
+    pub reset_after: Duration,
+    /// time until a request would be allowed, zero if allowed
+    pub retry_after: Duration,
+}
+
+// the allowed requests counted in the previous and in the current window
+#[derive(Debug, Clone, Copy, Default)]
+struct WindowCounts {
+    previous: u64,
+    current: u64,
+}
+
+/// Counts the request of the client against the rule.
+/// The Redis counters are shared by the instances, the in-memory ones are used if Redis fails.
+pub async fn check(
+    state: &SharedState,
+    store: RateLimitStore,
+    rule: &RateLimitRule,
+    client: &str
+) -> RateLimitDecision {
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    let key = format!("{}.{}.{}", RATE_LIMIT_REDIS_KEY_PREFIX, rule.path, client);
+
+    if store == RateLimitStore::Redis {
+        // a slow redis does not hold up the requests
+        let redis_timeout = Duration::from_millis(state.config().rate_limit_redis_timeout_milliseconds);
+        match timeout(redis_timeout, check_redis(state, rule, &key, now_ms)).await {
+            Ok(Ok(decision)) => {
+                return decision;
+            }
+            Ok(Err(e)) => {
+                tracing::error!("rate limit falls back to the in-memory store: {}", e);
+                metrics_service::record_redis_error("rate_limit");
+            }
+            Err(_) => {
+                tracing::error!("rate limit falls back to the in-memory store: redis timed out");
+                metrics_service::record_redis_error("rate_limit");
+            }
+        }
+    }
+    state.rate_limits.check(rule, &key, now_ms)
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "rate_limit_service::check_redis",
+    skip_all,
+    fields(db.system = "redis", db.operation = "INCR PEXPIRE GET")
+)]
+async fn check_redis(
+    state: &SharedState,
+    rule: &RateLimitRule,
+    key: &str,
+    now_ms: u64
+) -> RedisResult<RateLimitDecision> {
+    let window_ms = rule.window_seconds * 1000;
+    let window = now_ms / window_ms;
+    let current_key = format!("{}.{}", key, window);
+    let previous_key = format!("{}.{}", key, window - 1);
+
+    // the request is counted first, so the concurrent requests of the instances see each other
+    let mut redis = state.redis.lock().await;
+    let (current, previous): (u64, Option<u64>) = redis::pipe()
+        .incr(&current_key, 1)
+        .pexpire(&current_key, (2 * window_ms) as i64)
+        .ignore()
+        .get(&previous_key)
+        .query_async(&mut *redis).await?;
+
+    let counts = WindowCounts {
+        previous: previous.unwrap_or_default(),
+        current: current - 1,
+    };
+    let decision = decide(rule, now_ms, counts);
+    if !decision.allowed {
+        // only the allowed requests are counted
+        if let Err(e) = redis.decr::<_, _, ()>(&current_key, 1).await {
+            tracing::error!("{}", e);
+            metrics_service::record_redis_error("rate_limit");
+        }
+    }
+    Ok(decision)
+}
+
+/// The rate limit counters of this instance
+#[derive(Default)]
+pub struct MemoryRateLimits {
+    clients: Mutex<HashMap<String, MemoryWindow>>,
+}
+
+struct MemoryWindow {
+    window: u64,
+    window_ms: u64,
+    counts: WindowCounts,
+}
+
+impl MemoryRateLimits {
+    fn check(&self, rule: &RateLimitRule, key: &str, now_ms: u64) -> RateLimitDecision {
+        let window_ms = rule.window_seconds * 1000;
+        let window = now_ms / window_ms;
+
+        let mut clients = self.clients.lock().unwrap();
+        if clients.len() >= MEMORY_MAX_CLIENTS {
+            // forget the clients without requests in the sliding window
+            clients.retain(|_, client| (client.window + 2) * client.window_ms > now_ms);
+        }
+        let client = clients.entry(key.to_string()).or_insert(MemoryWindow {
+            window,
+            window_ms,
+            counts: WindowCounts::default(),
+        });
+        if client.window != window || client.window_ms != window_ms {
+            let previous = if client.window + 1 == window && client.window_ms == window_ms {
+                client.counts.current
+            } else {
+                0
+            };
+            *client = MemoryWindow {
+                window,
+                window_ms,
+                counts: WindowCounts { previous, current: 0 },
+            };
+        }
+
+        let decision = decide(rule, now_ms, client.counts);
+        if decision.allowed {
+            client.counts.current += 1;
+        }
+        decision
+    }
+}
+
+// sliding window counter: the requests of the previous window are weighted
+// by the share of the previous window still covered by the sliding window
+fn decide(rule: &RateLimitRule, now_ms: u64, counts: WindowCounts) -> RateLimitDecision {
+    let limit = rule.requests as f64;
+    let window_ms = (rule.window_seconds * 1000) as f64;
+    let until_next_window = window_ms - (now_ms as f64 % window_ms);
+    let previous = counts.previous as f64;
+    let current = counts.current as f64;
+
+    let estimate = (previous * until_next_window) / window_ms + current + 1.0;
+    let allowed = estimate <= limit;
+
+    let retry_after = if allowed {
+        0.0
+    } else if current + 1.0 <= limit {
+        // the weight of the previous window decreases enough in the current window
+        until_next_window - ((limit - current - 1.0) * window_ms) / previous
+    } else {
+        // the current window becomes the previous one
+        until_next_window + window_ms * (1.0 - (limit - 1.0) / current)
+    };
+
+    RateLimitDecision {
+        allowed,
+        limit: rule.requests,
+        remaining: if allowed { (limit - estimate).floor() as u64 } else { 0 },
+        reset_after: Duration::from_millis(until_next_window as u64),
+        retry_after: Duration::from_millis(retry_after.max(0.0).ceil() as u64),
+    }
+}
@@ -2,26 +2,44 @@ use redis::{aio::Connection, AsyncCommands, RedisResult};
 use std::collections::HashMap;
 use tokio::sync::MutexGuard;
 
-use super::{app_const::*, security::jwt_claims::{RefreshClaims, ClaimsMethods}};
+use super::{
+    app_const::*,
+    metrics_service,
+    security::jwt_claims::{ClaimsMethods, RefreshClaims},
+};
 use crate::application::state::SharedState;
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "redis_service::revoke_global",
+    skip_all,
+    fields(db.system = "redis", db.operation = "SET")
+)]
 pub async fn revoke_global(state: &SharedState) -> bool {
-    let timestamp_now = chrono::Utc::now().timestamp() as usize;
+    let timestamp_now = revocation_timestamp_now();
     tracing::debug!("setting a timestamp for global revoke: {}", timestamp_now);
 
     let mut redis = state.redis.lock().await;
     let redis_result: RedisResult<()> = redis
-        .set(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY, timestamp_now)
+        .set(JWT_REDIS_REVOKE_GLOBAL_BEFORE_KEY, &timestamp_now)
         .await;
     if let Err(e) = redis_result {
         tracing::error!("{}", e);
+        metrics_service::record_redis_error("revoke_global");
         return false;
     }
+    metrics_service::record_tokens_revoked("global", 1);
     true
 }
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "redis_service::revoke_user_tokens",
+    skip_all,
+    fields(db.system = "redis", db.operation = "HSET", user_id = %user_id)
+)]
 pub async fn revoke_user_tokens(user_id: &str, state: &SharedState) -> bool {
-    let timestamp_now = chrono::Utc::now().timestamp() as usize;
+    let timestamp_now = revocation_timestamp_now();
     tracing::debug!(
         "adding a timestamp for user revoke, user:{}, timestamp: {}",
         user_id,
@@ -30,15 +48,35 @@ pub async fn revoke_user_tokens(user_id: &str, state: &SharedState) -> bool {
 
     let mut redis = state.redis.lock().await;
     let redis_result: RedisResult<()> = redis
-        .hset(JWT_REDIS_REVOKE_USER_BEFORE_KEY, user_id, timestamp_now)
+        .hset(JWT_REDIS_REVOKE_USER_BEFORE_KEY, user_id, &timestamp_now)
         .await;
     if let Err(e) = redis_result {
         tracing::error!("{}", e);
+        metrics_service::record_redis_error("revoke_user_tokens");
         return false;
     }
+    metrics_service::record_tokens_revoked("user", 1);
     true
 }
 
+// the revocation timestamps are stored as seconds with the microseconds as the fraction,
+// so that tokens issued right after a revocation are not caught by it
+fn revocation_timestamp_now() -> String {
+    let now = chrono::Utc::now();
+    format!("{}.{:06}", now.timestamp(), now.timestamp_subsec_micros().min(999_999))
+}
+
+// returns the revocation timestamp in microseconds, the timestamps stored in whole seconds are accepted too
+fn parse_revocation_timestamp(timestamp: &str) -> Option<i64> {
+    let (seconds, fraction) = timestamp.split_once('.').unwrap_or((timestamp, "0"));
+    if fraction.is_empty() || fraction.len() > 6 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let seconds = seconds.parse::<i64>().ok()?;
+    let micros = format!("{:0<6}", fraction).parse::<i64>().ok()?;
+    Some(seconds * 1_000_000 + micros)
+}
+
 async fn is_global_revoked<T: ClaimsMethods>(
     claims: &T,
     redis: &mut MutexGuard<'_, redis::aio::Connection>,
@@ -49,14 +87,14 @@ async fn is_global_revoked<T: ClaimsMethods>(
     match redis_result {
         Ok(opt_exp) => {
             if let Some(exp) = opt_exp {
-                match exp.parse::<usize>() {
-                    Ok(global_exp) => {
-                        if global_exp >= claims.get_iat() {
+                match parse_revocation_timestamp(&exp) {
+                    Some(revoked_before) => {
+                        if revoked_before >= claims.get_iat_micros() {
                             return Some(true);
                         }
                     }
-                    Err(e) => {
-                        tracing::error!("{}", e);
+                    None => {
+                        tracing::error!("invalid revocation timestamp: {}", exp);
                         return None;
                     }
                 }
@@ -64,6 +102,7 @@ async fn is_global_revoked<T: ClaimsMethods>(
         }
         Err(e) => {
             tracing::error!("{}", e);
+            metrics_service::record_redis_error("is_global_revoked");
             return None;
         }
     }
@@ -81,14 +120,14 @@ async fn is_user_revoked<T: ClaimsMethods>(
     match redis_result {
         Ok(opt_exp) => {
             if let Some(exp) = opt_exp {
-                match exp.parse::<usize>() {
-                    Ok(global_exp) => {
-                        if global_exp >= claims.get_iat() {
+                match parse_revocation_timestamp(&exp) {
+                    Some(revoked_before) => {
+                        if revoked_before >= claims.get_iat_micros() {
                             return Some(true);
                         }
                     }
-                    Err(e) => {
-                        tracing::error!("{}", e);
+                    None => {
+                        tracing::error!("invalid revocation timestamp: {}", exp);
                         return None;
                     }
                 }
@@ -96,6 +135,7 @@ async fn is_user_revoked<T: ClaimsMethods>(
         }
         Err(e) => {
             tracing::error!("{}", e);
+            metrics_service::record_redis_error("is_user_revoked");
             return None;
         }
     }
@@ -114,12 +154,26 @@ async fn is_token_revoked<T: ClaimsMethods>(
         Ok(revoked) => Some(revoked),
         Err(e) => {
             tracing::error!("{}", e);
+            metrics_service::record_redis_error("is_token_revoked");
             None
         }
     }
 }
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "redis_service::is_revoked",
+    skip_all,
+    fields(db.system = "redis", db.operation = "GET HGET HEXISTS", jti = %claims.get_jti())
+)]
 pub async fn is_revoked<T: std::fmt::Debug + ClaimsMethods>(claims: &T, state: &SharedState) -> Option<bool> {
+    let started = std::time::Instant::now();
+    let revoked = check_revoked(claims, state).await;
+    metrics_service::record_revocation_check(started.elapsed());
+    revoked
+}
+
+async fn check_revoked<T: std::fmt::Debug + ClaimsMethods>(claims: &T, state: &SharedState) -> Option<bool> {
     let mut redis = state.redis.lock().await;
     match is_global_revoked(claims, &mut redis).await {
         Some(revoked) => {
@@ -160,6 +214,12 @@ pub async fn is_revoked<T: std::fmt::Debug + ClaimsMethods>(claims: &T, state: &
     Some(false)
 }
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "redis_service::revoke_refresh_token",
+    skip_all,
+    fields(db.system = "redis", db.operation = "HSET", jti = %claims.jti)
+)]
 pub async fn revoke_refresh_token(claims: &RefreshClaims, state: &SharedState) -> bool {
     // adds the both refersh token and its paired access token into revoked list in Redis
     // tokens are tracked by JWT ID that handles the cases of reusing lost tokens and multi-device scenarios
@@ -173,9 +233,11 @@ pub async fn revoke_refresh_token(claims: &RefreshClaims, state: &SharedState) -
             .await;
         if let Err(e) = redis_result {
             tracing::error!("{}", e);
+            metrics_service::record_redis_error("revoke_refresh_token");
             return false;
         }
     }
+    metrics_service::record_tokens_revoked("token", 2);
 
     if tracing::enabled!(tracing::Level::TRACE) {
         log_revoked_tokens_count(&mut redis).await;
@@ -183,6 +245,12 @@ pub async fn revoke_refresh_token(claims: &RefreshClaims, state: &SharedState) -
     true
 }
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "redis_service::cleanup_expired",
+    skip_all,
+    fields(db.system = "redis", db.operation = "HGETALL HDEL")
+)]
 pub async fn cleanup_expired(state: &SharedState) -> Option<usize> {
     match delete_expired_tokens(state).await {
         Ok(deleted) => {
@@ -194,6 +262,7 @@ pub async fn cleanup_expired(state: &SharedState) -> Option<usize> {
         }
         Err(e) => {
             tracing::error!("{}", e);
+            metrics_service::record_redis_error("cleanup_expired");
             None
         }
     }
@@ -211,7 +280,7 @@ async fn delete_expired_tokens(state: &SharedState) -> RedisResult<usize> {
         match exp.parse::<usize>() {
             Ok(timestamp_exp) => {
                 if timestamp_now > timestamp_exp {
-                    redis.hdel(JWT_REDIS_REVOKED_TOKENS_KEY, key).await?;
+                    redis.hdel::<_, _, ()>(JWT_REDIS_REVOKED_TOKENS_KEY, key).await?;
                     deleted += 1;
                 }
             }
@@ -0,0 +1,230 @@
+use chrono::{ NaiveDateTime, Utc };
+use hyper::StatusCode;
+use std::net::IpAddr;
+use uuid::Uuid;
+
+use crate::{ domain::models::user::User, infrastructure::mailer::Mail };
+
+use super::{
+    api_error::{ ApiError, ApiErrorType },
+    api_validation::{ is_valid_email, FieldError },
+    app_const::USER_ROLE_GUEST,
+    audit_service::{ self, AuditEvent },
+    config::RegistrationPolicy,
+    email_verification_service,
+    repository::{ invite_repo, user_repo },
+    security::{ one_time_token, password, password_policy },
+    state::SharedState,
+};
+
+// The users register themselves as guests following `REGISTRATION_POLICY`,
+// the invite codes are issued by the admins, single-use and expire after `REGISTRATION_INVITE_SECONDS`.
+// The email of a registered user is verified like the one of a user created by an admin.
+// A taken email is answered like a new user and its owner is notified, so the registered emails cannot be told apart,
+// except by the missing tokens with `REGISTRATION_AUTO_LOGIN` when the email does not have to be verified.
+
+/// The registration request
+pub struct Registration<'a> {
+    pub username: &'a str,
+    pub email: &'a str,
+    pub password: &'a str,
+    pub invite_code: Option<&'a str>,
+    pub captcha_response: Option<&'a str>,
+}
+
+/// The outcome of an accepted registration
+pub enum Registered {
+    Created(User),
+    /// the user that would have been created, the owner of the email is notified instead
+    EmailTaken(User),
+}
+
+/// Creates a guest user if the registration is allowed, the problems of the fields are reported together
+pub async fn register(
+    registration: Registration<'_>,
+    ip: Option<IpAddr>,
+    state: &SharedState
+) -> Result<Registered, ApiError> {
+    let config = state.config();
+    if config.registration_policy == RegistrationPolicy::Disabled {
+        return Err(ApiError {
+            status_code: StatusCode::NOT_FOUND,
+            error_type: ApiErrorType::Api,
+            error_message: "Registration is disabled".to_string(),
+        });
+    }
+
+    // checked first, so the bots do not get to probe the usernames and the emails
+    if let Some(captcha) = &state.captcha {
+        match captcha.verify(registration.captcha_response.unwrap_or_default(), ip).await {
+            Ok(true) => {}
+            Ok(false) => {
+                let error = FieldError::new("captcha_response", "invalid", "is missing or not solved");
+                return Err(vec![error].into());
+            }
+            Err(e) => {
+                tracing::error!("captcha not verified: {}", e);
+                return Err(StatusCode::SERVICE_UNAVAILABLE.into());
+            }
+        }
+    }
+
+    let username = registration.username.trim();
+    let email = registration.email.trim();
+    let mut errors = Vec::new();
+    if username.is_empty() {
+        errors.push(FieldError::new("username", "required", "must not be empty"));
+    } else if user_repo::get_user_by_username(username, state).await.is_some() {
+        errors.push(FieldError::new("username", "taken", "is already in use"));
+    }
+    if !is_valid_email(email) {
+        errors.push(FieldError::new("email", "invalid", "must be a valid email address"));
+    }
+    // not reported, the registered emails are not disclosed
+    let email_taken = errors.is_empty() && user_repo::get_user_by_email(email, state).await.is_some();
+
+    let invite_hash = registration.invite_code.map(one_time_token::hash);
+    match config.registration_policy {
+        RegistrationPolicy::DomainAllowlist => {
+            if !is_allowed_domain(email, &config.registration_allowed_domains) {
+                errors.push(FieldError::new("email", "domain_not_allowed", "is not of an allowed domain"));
+            }
+        }
+        RegistrationPolicy::InviteOnly =>
+            match &invite_hash {
+                None => errors.push(FieldError::new("invite_code", "required", "must not be empty")),
+                Some(invite_hash) if !invite_repo::is_valid(invite_hash, email, state).await => {
+                    errors.push(FieldError::new("invite_code", "invalid", "is invalid, expired or already used"));
+                }
+                Some(_) => {}
+            }
+        RegistrationPolicy::Open | RegistrationPolicy::Disabled => {}
+    }
+
+    errors.extend(
+        password_policy::validate(
+            "password",
+            registration.password,
+            username,
+            email,
+            &config,
+            &state.breached_passwords
+        )
+    );
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    let user = User {
+        id: Uuid::new_v4(),
+        username: username.to_string(),
+        email: email.to_string(),
+        password: registration.password.to_string(),
+        active: true,
+        roles: USER_ROLE_GUEST.to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    };
+    if email_taken {
+        // as slow as a registration
+        password::hash_password(registration.password.as_bytes(), &config);
+        return Ok(email_taken_answer(user, state));
+    }
+
+    // used up only with the user added, a rejected registration keeps the invite valid
+    let invite_hash = match config.registration_policy {
+        RegistrationPolicy::InviteOnly => invite_hash.as_deref(),
+        _ => None,
+    };
+    let user = match user_repo::add_user_with_invite(user.clone(), invite_hash, state).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            let error = FieldError::new("invite_code", "invalid", "is invalid, expired or already used");
+            return Err(vec![error].into());
+        }
+        // registered concurrently
+        Err(e) if user_repo::taken_field(&e) == Some("username") => {
+            return Err(vec![FieldError::new("username", "taken", "is already in use")].into());
+        }
+        Err(e) if user_repo::taken_field(&e) == Some("email") => {
+            return Ok(email_taken_answer(user, state));
+        }
+        Err(e) => {
+            tracing::error!("{}", e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+        }
+    };
+    state.shutdown.spawn_task(
+        "email verification mail",
+        email_verification_service::send_verification(user.clone(), state.clone())
+    );
+    audit_service::record(AuditEvent::UserRegistered { user_id: &user.id.to_string(), ip });
+    Ok(Registered::Created(user))
+}
+
+// the user is not added, it looks like a new one to the caller
+fn email_taken_answer(mut user: User, state: &SharedState) -> Registered {
+    let time_now = Utc::now().naive_utc();
+    user.created_at = Some(time_now);
+    user.updated_at = Some(time_now);
+    state.shutdown.spawn_task(
+        "registration attempt mail",
+        notify_owner(user.email.clone(), state.clone())
+    );
+    Registered::EmailTaken(user)
+}
+
+// tells the owner of the email that somebody tried to register with it
+async fn notify_owner(email: String, state: SharedState) {
+    let Some(owner) = user_repo::get_user_by_email(&email, &state).await else {
+        return;
+    };
+    let mail = Mail {
+        from: state.config().mailer_from.clone(),
+        to: owner.email,
+        subject: "Registration attempt".to_string(),
+        body: format!(
+            "Somebody tried to register a new account with this email, which is already used by your account {}.\n\n\
+             If it was you, log in or reset your password instead.\n\
+             If it was not you, ignore this message.\n",
+            owner.username
+        ),
+    };
+    if let Err(e) = state.mailer.send(&mail).await {
+        tracing::error!("registration attempt mail not sent: {}", e);
+    }
+}
+
+/// Issues an invite code, for the given email only when set, the code and its expiry are returned
+pub async fn create_invite(
+    email: Option<&str>,
+    admin_id: &str,
+    state: &SharedState
+) -> Result<(String, NaiveDateTime), ApiError> {
+    let email = email.map(str::trim);
+    if email.is_some_and(|email| !is_valid_email(email)) {
+        let error = FieldError::new("email", "invalid", "must be a valid email address");
+        return Err(vec![error].into());
+    }
+    let Ok(created_by) = admin_id.parse::<Uuid>() else {
+        return Err(StatusCode::BAD_REQUEST.into());
+    };
+
+    let config = state.config();
+    let invite_code = one_time_token::generate();
+    let expires_at = (
+        Utc::now() + chrono::Duration::seconds(config.registration_invite_seconds as i64)
+    ).naive_utc();
+    if !invite_repo::add_invite(&one_time_token::hash(&invite_code), email, created_by, expires_at, state).await {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    audit_service::record(AuditEvent::InviteCreated { admin_id });
+    Ok((invite_code, expires_at))
+}
+
+fn is_allowed_domain(email: &str, allowed_domains: &[String]) -> bool {
+    email
+        .rsplit_once('@')
+        .is_some_and(|(_, domain)| allowed_domains.contains(&domain.to_lowercase()))
+}
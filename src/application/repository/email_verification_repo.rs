@@ -0,0 +1,72 @@
+use chrono::{ NaiveDateTime, Utc };
+use uuid::Uuid;
+
+use crate::application::state::SharedState;
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "email_verification_repo::add_token",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "INSERT", user_id = %user_id)
+)]
+pub async fn add_token(
+    user_id: Uuid,
+    email: &str,
+    token_hash: &str,
+    expires_at: NaiveDateTime,
+    state: &SharedState
+) -> bool {
+    let time_now = Utc::now().naive_utc();
+    let query_add = sqlx
+        ::query(
+            "INSERT INTO email_verification_tokens (token_hash, user_id, email, expires_at, created_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(email)
+        .bind(expires_at)
+        .bind(time_now)
+        .execute(&state.pgpool).await;
+
+    match query_add {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!("{}", e);
+            false
+        }
+    }
+}
+
+/// Uses up an unused and unexpired token and marks the email of the user as verified,
+/// if it is still the email the token was sent to, the user is returned when verified
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "email_verification_repo::verify",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE")
+)]
+pub async fn verify(token_hash: &str, state: &SharedState) -> Option<Uuid> {
+    let time_now = Utc::now().naive_utc();
+    let query_update = sqlx
+        ::query_scalar::<_, Uuid>(
+            r#"WITH token AS (
+            UPDATE email_verification_tokens SET used_at = $2
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+            RETURNING user_id, email)
+         UPDATE users SET email_verified_at = $2
+         FROM token
+         WHERE users.id = token.user_id AND users.email = token.email
+         RETURNING users.id"#
+        )
+        .bind(token_hash)
+        .bind(time_now)
+        .fetch_optional(&state.pgpool).await;
+
+    match query_update {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use chrono::{ NaiveDateTime, Utc };
+use sqlx::PgConnection;
+use uuid::Uuid;
+
+use crate::application::state::SharedState;
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "invite_repo::add_invite",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "INSERT", created_by = %created_by)
+)]
+pub async fn add_invite(
+    token_hash: &str,
+    email: Option<&str>,
+    created_by: Uuid,
+    expires_at: NaiveDateTime,
+    state: &SharedState
+) -> bool {
+    let time_now = Utc::now().naive_utc();
+    let query_add = sqlx
+        ::query(
+            "INSERT INTO registration_invites (token_hash, email, created_by, expires_at, created_at) VALUES ($1, $2, $3, $4, $5)"
+        )
+        .bind(token_hash)
+        .bind(email)
+        .bind(created_by)
+        .bind(expires_at)
+        .bind(time_now)
+        .execute(&state.pgpool).await;
+
+    match query_add {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!("{}", e);
+            false
+        }
+    }
+}
+
+/// Whether the invite is unused, unexpired and issued for the email or for any email
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "invite_repo::is_valid",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT")
+)]
+pub async fn is_valid(token_hash: &str, email: &str, state: &SharedState) -> bool {
+    let time_now = Utc::now().naive_utc();
+    let query_get = sqlx
+        ::query_scalar::<_, bool>(
+            r#"SELECT EXISTS (SELECT 1 FROM registration_invites
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+         AND (email IS NULL OR lower(email) = lower($3)))"#
+        )
+        .bind(token_hash)
+        .bind(time_now)
+        .bind(email)
+        .fetch_one(&state.pgpool).await;
+
+    match query_get {
+        Ok(valid) => valid,
+        Err(e) => {
+            tracing::error!("{}", e);
+            false
+        }
+    }
+}
+
+/// Marks the invite as used in the transaction of the registration,
+/// only one of the concurrent requests gets `true`
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "invite_repo::use_invite",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE")
+)]
+pub async fn use_invite(token_hash: &str, email: &str, connection: &mut PgConnection) -> sqlx::Result<bool> {
+    let time_now = Utc::now().naive_utc();
+    let result = sqlx
+        ::query(
+            r#"UPDATE registration_invites SET used_at = $2
+         WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+         AND (email IS NULL OR lower(email) = lower($3))"#
+        )
+        .bind(token_hash)
+        .bind(time_now)
+        .bind(email)
+        .execute(connection).await?;
+    Ok(result.rows_affected() == 1)
+}
@@ -1 +1,4 @@
+pub mod email_verification_repo;
+pub mod invite_repo;
+pub mod password_reset_repo;
 pub mod user_repo;
\ No newline at end of file
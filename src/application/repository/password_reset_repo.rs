@@ -0,0 +1,94 @@
+use chrono::{ NaiveDateTime, Utc };
+use uuid::Uuid;
+
+use crate::application::state::SharedState;
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "password_reset_repo::add_token",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "INSERT", user_id = %user_id)
+)]
+pub async fn add_token(
+    user_id: Uuid,
+    token_hash: &str,
+    expires_at: NaiveDateTime,
+    state: &SharedState
+) -> bool {
+    let time_now = Utc::now().naive_utc();
+    let query_add = sqlx
+        ::query(
+            "INSERT INTO password_reset_tokens (token_hash, user_id, expires_at, created_at) VALUES ($1, $2, $3, $4)"
+        )
+        .bind(token_hash)
+        .bind(user_id)
+        .bind(expires_at)
+        .bind(time_now)
+        .execute(&state.pgpool).await;
+
+    match query_add {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!("{}", e);
+            false
+        }
+    }
+}
+
+/// The user of an unused and unexpired token
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "password_reset_repo::get_user_id",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT")
+)]
+pub async fn get_user_id(token_hash: &str, state: &SharedState) -> Option<Uuid> {
+    let time_now = Utc::now().naive_utc();
+    let query_get = sqlx
+        ::query_scalar::<_, Uuid>(
+            "SELECT user_id FROM password_reset_tokens WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2"
+        )
+        .bind(token_hash)
+        .bind(time_now)
+        .fetch_optional(&state.pgpool).await;
+
+    match query_get {
+        Ok(user_id) => user_id,
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
+
+/// Marks the token as used together with the other tokens of the user,
+/// only one of the concurrent requests gets the user of the token
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "password_reset_repo::use_token",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE")
+)]
+pub async fn use_token(token_hash: &str, state: &SharedState) -> Option<Uuid> {
+    let time_now = Utc::now().naive_utc();
+    let query_update = sqlx
+        ::query_scalar::<_, Uuid>(
+            r#"UPDATE password_reset_tokens SET used_at = $2
+         WHERE used_at IS NULL AND user_id = (
+            SELECT user_id FROM password_reset_tokens
+            WHERE token_hash = $1 AND used_at IS NULL AND expires_at > $2
+            FOR UPDATE)
+         RETURNING user_id"#
+        )
+        .bind(token_hash)
+        .bind(time_now)
+        .fetch_all(&state.pgpool).await;
+
+    match query_update {
+        Ok(user_ids) => user_ids.into_iter().next(),
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
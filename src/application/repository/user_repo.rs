@@ -1,12 +1,27 @@
 use chrono::Utc;
-use sqlx::query_as;
+use sqlx::{ query_as, PgExecutor };
 use uuid::Uuid;
 
 use crate::{
-    application::{ app_const::USER_ROLE_GUEST, security::password, state::SharedState },
+    application::{
+        app_const::USER_ROLE_GUEST,
+        repository::invite_repo,
+        security::password,
+        state::SharedState,
+    },
     domain::models::user::User,
 };
 
+// the unique indexes of the lowercase usernames and emails
+const USERNAME_UNIQUE_INDEX: &str = "users_username_lower_key";
+const EMAIL_UNIQUE_INDEX: &str = "users_email_lower_key";
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::all_users",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT")
+)]
 pub async fn all_users(state: &SharedState) -> Option<Vec<User>> {
     match query_as::<_, User>("SELECT * FROM users").fetch_all(&state.pgpool).await {
         Ok(users) => Some(users),
@@ -17,13 +32,66 @@ pub async fn all_users(state: &SharedState) -> Option<Vec<User>> {
     }
 }
 
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::add_user",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "INSERT", user_id = %user.id)
+)]
 pub async fn add_user(user: User, state: &SharedState) -> Option<User> {
-    let time_now = Utc::now().naive_utc();
     tracing::trace!("user: {:#?}", user);
 
-    let password = password::hash_password(user.password.as_bytes());
+    let password = password::hash_password(user.password.as_bytes(), &state.config());
+
+    match insert_user(user, password, &state.pgpool).await {
+        Ok(user) => Some(user),
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
+
+/// Adds the user and uses the invite in one transaction, nothing is written if either fails.
+/// `None` if the invite cannot be used, see `taken_field` for the errors of a taken username or email.
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::add_user_with_invite",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "INSERT", user_id = %user.id)
+)]
+pub async fn add_user_with_invite(
+    user: User,
+    invite_hash: Option<&str>,
+    state: &SharedState
+) -> sqlx::Result<Option<User>> {
+    tracing::trace!("user: {:#?}", user);
+
+    let password = password::hash_password(user.password.as_bytes(), &state.config());
 
-    let query_add = sqlx
+    let mut transaction = state.pgpool.begin().await?;
+    if let Some(invite_hash) = invite_hash {
+        if !invite_repo::use_invite(invite_hash, &user.email, &mut transaction).await? {
+            return Ok(None);
+        }
+    }
+    let user = insert_user(user, password, &mut *transaction).await?;
+    transaction.commit().await?;
+    Ok(Some(user))
+}
+
+/// The field of the unique index violated by the error, `username` or `email`
+pub fn taken_field(e: &sqlx::Error) -> Option<&'static str> {
+    match e.as_database_error()?.constraint()? {
+        USERNAME_UNIQUE_INDEX => Some("username"),
+        EMAIL_UNIQUE_INDEX => Some("email"),
+        _ => None,
+    }
+}
+
+async fn insert_user(user: User, password: String, executor: impl PgExecutor<'_>) -> sqlx::Result<User> {
+    let time_now = Utc::now().naive_utc();
+    sqlx
         ::query_as::<_, User>(
             r#"INSERT INTO users (id,
          username,
@@ -33,7 +101,7 @@ pub async fn add_user(user: User, state: &SharedState) -> Option<User> {
          roles,
          created_at,
          updated_at)
-         VALUES ($1,$2,$3,$4,$5,$6,$7,$8,$9)
+         VALUES ($1,$2,$3,$4,$5,$6,$7,$8)
          RETURNING users.*"#
         )
         .bind(user.id)
@@ -44,9 +112,22 @@ pub async fn add_user(user: User, state: &SharedState) -> Option<User> {
         .bind(USER_ROLE_GUEST)
         .bind(time_now)
         .bind(time_now)
+        .fetch_one(executor).await
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::get_user",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT", user_id = %id)
+)]
+pub async fn get_user(id: Uuid, state: &SharedState) -> Option<User> {
+    let query_get = sqlx
+        ::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
+        .bind(id)
         .fetch_one(&state.pgpool).await;
 
-    match query_add {
+    match query_get {
         Ok(user) => Some(user),
         Err(e) => {
             tracing::error!("{}", e);
@@ -55,10 +136,17 @@ pub async fn add_user(user: User, state: &SharedState) -> Option<User> {
     }
 }
 
-pub async fn get_user(id: Uuid, state: &SharedState) -> Option<User> {
+/// The username regardless of its case, the usernames are unique regardless of their case
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::get_user_by_username",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT", username = %username)
+)]
+pub async fn get_user_by_username(username: &str, state: &SharedState) -> Option<User> {
     let query_get = sqlx
-        ::query_as::<_, User>("SELECT * FROM users WHERE id = $1")
-        .bind(id)
+        ::query_as::<_, User>("SELECT * FROM users WHERE lower(username) = lower($1)")
+        .bind(username)
         .fetch_one(&state.pgpool).await;
 
     match query_get {
@@ -70,14 +158,21 @@ pub async fn get_user(id: Uuid, state: &SharedState) -> Option<User> {
     }
 }
 
-pub async fn get_user_by_username(username: &str, state: &SharedState) -> Option<User> {
+/// The email regardless of its case, the emails are unique regardless of their case
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::get_user_by_email",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "SELECT")
+)]
+pub async fn get_user_by_email(email: &str, state: &SharedState) -> Option<User> {
     let query_get = sqlx
-        ::query_as::<_, User>("SELECT * FROM users WHERE username = $1")
-        .bind(username)
-        .fetch_one(&state.pgpool).await;
+        ::query_as::<_, User>("SELECT * FROM users WHERE lower(email) = lower($1)")
+        .bind(email)
+        .fetch_optional(&state.pgpool).await;
 
     match query_get {
-        Ok(user) => Some(user),
+        Ok(user) => user,
         Err(e) => {
             tracing::error!("{}", e);
             None
@@ -85,6 +180,13 @@ pub async fn get_user_by_username(username: &str, state: &SharedState) -> Option
     }
 }
 
+/// A changed email is no longer verified
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::update_user",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %id)
+)]
 pub async fn update_user(id: Uuid, user: User, state: &SharedState) -> Option<User> {
     tracing::trace!("user: {:#?}", user);
     let time_now = Utc::now().naive_utc();
@@ -95,10 +197,11 @@ pub async fn update_user(id: Uuid, user: User, state: &SharedState) -> Option<Us
          username = $2,
          email = $3,
          password = $4,
-         active = $6,
-         roles = $7,
-         updated_at = $8
-         WHERE id = $9
+         active = $5,
+         roles = $6,
+         updated_at = $7,
+         email_verified_at = CASE WHEN email = $3 THEN email_verified_at END
+         WHERE id = $8
          RETURNING users.*"#
         )
         .bind(user.id)
@@ -120,6 +223,70 @@ pub async fn update_user(id: Uuid, user: User, state: &SharedState) -> Option<Us
     }
 }
 
+/// A changed email is no longer verified
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::update_profile",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %id)
+)]
+pub async fn update_profile(id: Uuid, username: &str, email: &str, state: &SharedState) -> Option<User> {
+    let time_now = Utc::now().naive_utc();
+    let query_update = sqlx
+        ::query_as::<_, User>(
+            r#"UPDATE users
+         SET username = $1,
+         email = $2,
+         updated_at = $3,
+         email_verified_at = CASE WHEN email = $2 THEN email_verified_at END
+         WHERE id = $4
+         RETURNING users.*"#
+        )
+        .bind(username)
+        .bind(email)
+        .bind(time_now)
+        .bind(id)
+        .fetch_one(&state.pgpool).await;
+
+    match query_update {
+        Ok(user) => Some(user),
+        Err(e) => {
+            tracing::error!("{}", e);
+            None
+        }
+    }
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::update_password",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "UPDATE", user_id = %id)
+)]
+pub async fn update_password(id: Uuid, password_hash: &str, state: &SharedState) -> bool {
+    let time_now = Utc::now().naive_utc();
+    let query_update = sqlx
+        ::query("UPDATE users SET password = $1, updated_at = $2 WHERE id = $3")
+        .bind(password_hash)
+        .bind(time_now)
+        .bind(id)
+        .execute(&state.pgpool).await;
+
+    match query_update {
+        Ok(row) => row.rows_affected() == 1,
+        Err(e) => {
+            tracing::error!("{}", e);
+            false
+        }
+    }
+}
+
+#[tracing::instrument(
+    level = tracing::Level::DEBUG,
+    name = "user_repo::delete_user",
+    skip_all,
+    fields(db.system = "postgresql", db.operation = "DELETE", user_id = %id)
+)]
 pub async fn delete_user(id: Uuid, state: &SharedState) -> Option<bool> {
     let query_delete = sqlx
         ::query("DELETE FROM users WHERE id = $1")
@@ -9,6 +9,8 @@ pub enum AuthError {
     TokenCreation,
     InvalidToken,
     ExpiredToken,
+    TooManyAttempts,
+    EmailNotVerified,
 }
 
 impl From<AuthError> for ApiError {
@@ -19,6 +21,8 @@ impl From<AuthError> for ApiError {
             AuthError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error"),
             AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Invalid token"),
             AuthError::ExpiredToken => (StatusCode::BAD_REQUEST, "Expired token"),
+            AuthError::TooManyAttempts => (StatusCode::TOO_MANY_REQUESTS, "Too many failed login attempts"),
+            AuthError::EmailNotVerified => (StatusCode::FORBIDDEN, "Email not verified"),
         };
         ApiError {
             status_code,
@@ -0,0 +1,55 @@
+use sha1::{ Digest, Sha1 };
+use std::{ collections::{ HashMap, HashSet }, fs::File, io::{ self, BufRead, BufReader } };
+
+// the length of the SHA-1 prefix the hashes are grouped by, as in the k-anonymity range APIs
+const PREFIX_LENGTH: usize = 5;
+
+/// The SHA-1 hashes of breached passwords grouped by their prefix,
+/// the corpus file has one uppercase or lowercase hex hash per line, optionally followed by `:<count>`
+/// as in the Pwned Passwords downloads
+#[derive(Debug, Default)]
+pub struct BreachedPasswords {
+    ranges: HashMap<String, HashSet<String>>,
+    len: usize,
+}
+
+impl BreachedPasswords {
+    pub fn load(path: &str) -> io::Result<Self> {
+        Self::read(BufReader::new(File::open(path)?))
+    }
+
+    /// Reads the hashes, the lines which are not a SHA-1 hash are skipped
+    pub fn read(reader: impl BufRead) -> io::Result<Self> {
+        let mut breached = Self::default();
+        for line in reader.lines() {
+            let line = line?;
+            let hash = line.split(':').next().unwrap_or_default().trim();
+            if hash.len() != 40 || !hash.chars().all(|c| c.is_ascii_hexdigit()) {
+                continue;
+            }
+            breached.insert(&hash.to_ascii_uppercase());
+        }
+        Ok(breached)
+    }
+
+    pub fn contains(&self, password: &str) -> bool {
+        let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+        self.ranges.get(prefix).is_some_and(|suffixes| suffixes.contains(suffix))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn insert(&mut self, hash: &str) {
+        let (prefix, suffix) = hash.split_at(PREFIX_LENGTH);
+        if self.ranges.entry(prefix.to_owned()).or_default().insert(suffix.to_owned()) {
+            self.len += 1;
+        }
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     application::{
         api_error::{ ApiError, ApiErrorType },
-        config,
+        config::Config,
+        metrics_service,
         redis_service,
         repository::user_repo,
         state::SharedState,
@@ -20,7 +21,7 @@ pub struct JwtTokens {
 
 pub async fn logout(refresh_claims: RefreshClaims, state: SharedState) -> Result<(), ApiError> {
     // checking the configuration if the usage of the list of revoked tokens is enabled
-    if config::get().jwt_enable_revoked_tokens {
+    if state.config().jwt_enable_revoked_tokens {
         // decode and validate the refresh token
         if !validate_token_type(&refresh_claims, JwtTokenType::RefreshToken) {
             return Err(AuthError::InvalidToken.into());
@@ -41,13 +42,13 @@ pub async fn refresh(
     }
 
     // checking the configuration if the usage of the list of revoked tokens is enabled
-    if config::get().jwt_enable_revoked_tokens {
+    if state.config().jwt_enable_revoked_tokens {
         revoke_refresh_token(&refresh_claims, &state).await?;
     }
 
     let user_id = refresh_claims.sub.parse().unwrap();
     if let Some(user) = user_repo::get_user(user_id, &state).await {
-        let tokens = generate_tokens(user);
+        let tokens = generate_tokens(user, &state.config());
         return Ok(tokens);
     }
 
@@ -63,7 +64,7 @@ pub async fn cleanup_revoked_and_expired(
     state: &SharedState
 ) -> Result<usize, ApiError> {
     // checking the configuration if the usage of the list of revoked tokens is enabled
-    if !config::get().jwt_enable_revoked_tokens {
+    if !state.config().jwt_enable_revoked_tokens {
         return Err(StatusCode::NOT_ACCEPTABLE.into());
     }
 
@@ -98,11 +99,18 @@ async fn revoke_refresh_token(
     Err(StatusCode::INTERNAL_SERVER_ERROR.into())
 }
 
-pub fn generate_tokens(user: User) -> JwtTokens {
-    let config = config::get();
+/// Revokes the tokens issued to the user until now and issues new ones for the current device
+pub async fn reissue(user: User, state: &SharedState) -> Result<JwtTokens, ApiError> {
+    if !redis_service::revoke_user_tokens(&user.id.to_string(), state).await {
+        return Err(StatusCode::INTERNAL_SERVER_ERROR.into());
+    }
+    Ok(generate_tokens(user, &state.config()))
+}
 
+pub fn generate_tokens(user: User, config: &Config) -> JwtTokens {
     let time_now = chrono::Utc::now();
     let iat = time_now.timestamp() as usize;
+    let ius = time_now.timestamp_subsec_micros();
     let sub = user.id.to_string();
 
     let access_token_id = Uuid::new_v4().to_string();
@@ -122,6 +130,7 @@ pub fn generate_tokens(user: User) -> JwtTokens {
         sub: sub.clone(),
         jti: access_token_id.clone(),
         iat,
+        ius,
         exp: access_token_exp,
         typ: JwtTokenType::AccessToken as u8,
         roles: user.roles.clone(),
@@ -131,6 +140,7 @@ pub fn generate_tokens(user: User) -> JwtTokens {
         sub,
         jti: refresh_token_id,
         iat,
+        ius,
         exp: (time_now + refresh_token_exp_config).timestamp() as usize,
         prf: access_token_id,
         pex: access_token_exp,
@@ -138,7 +148,7 @@ pub fn generate_tokens(user: User) -> JwtTokens {
         roles: user.roles,
     };
 
-    tracing::info!(
+    tracing::debug!(
         "JWT: generated claims\naccess {:#?}\nrefresh {:#?}",
         access_claims,
         refresh_claims
@@ -161,11 +171,7 @@ pub fn generate_tokens(user: User) -> JwtTokens {
         )
         .unwrap();
 
-    tracing::info!(
-        "JWT: generated tokens\naccess {:#?}\nrefresh {:#?}",
-        access_token,
-        refresh_token
-    );
+    metrics_service::record_tokens_issued(2);
 
     JwtTokens {
         access_token,
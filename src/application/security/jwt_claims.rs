@@ -9,7 +9,7 @@ use serde::{ Deserialize, Serialize };
 use std::sync::Arc;
 
 use crate::application::{
-    config,
+    config::Config,
     api_error::ApiError,
     security::{ self, auth_error::* },
     state::SharedState,
@@ -31,6 +31,9 @@ pub struct AccessClaims {
     pub jti: String,
     /// Issued At
     pub iat: usize,
+    /// Issued At, the microseconds within the second of `iat` for the revocation checks
+    #[serde(default)]
+    pub ius: u32,
     /// Expiration Time
     pub exp: usize,
     /// Token Type
@@ -47,6 +50,9 @@ pub struct RefreshClaims {
     pub jti: String,
     /// Issued At
     pub iat: usize,
+    /// Issued At, the microseconds within the second of `iat` for the revocation checks
+    #[serde(default)]
+    pub ius: u32,
     /// Expiration Time
     pub exp: usize,
     /// Reference to paired access token
@@ -81,6 +87,7 @@ pub trait ClaimsMethods {
     fn get_sub(&self) -> &str;
     fn get_exp(&self) -> usize;
     fn get_iat(&self) -> usize;
+    fn get_iat_micros(&self) -> i64;
     fn get_jti(&self) -> &str;
 }
 
@@ -96,6 +103,10 @@ impl ClaimsMethods for AccessClaims {
         self.iat
     }
 
+    fn get_iat_micros(&self) -> i64 {
+        (self.iat as i64) * 1_000_000 + (self.ius as i64)
+    }
+
     fn get_exp(&self) -> usize {
         self.exp
     }
@@ -116,6 +127,10 @@ impl ClaimsMethods for RefreshClaims {
         self.iat
     }
 
+    fn get_iat_micros(&self) -> i64 {
+        (self.iat as i64) * 1_000_000 + (self.ius as i64)
+    }
+
     fn get_exp(&self) -> usize {
         self.exp
     }
@@ -170,24 +185,27 @@ async fn decode_token_from_request_part<S, T>(parts: &mut Parts, state: &S) -> R
         })?;
 
     // decode the token
-    let claims = decode_token::<T>(bearer.token())?;
+    let shared_state: SharedState = Arc::from_ref(state);
+    let config = shared_state.config();
+    let claims = decode_token::<T>(bearer.token(), &config)?;
 
     // check for revoked tokens if enabled by configuration
-    if config::get().jwt_enable_revoked_tokens {
-        let shared_state: SharedState = Arc::from_ref(state);
+    if config.jwt_enable_revoked_tokens {
         jwt_auth::validate_revoked(&claims, &shared_state).await?;
     }
     Ok(claims)
 }
 
-pub fn decode_token<T: for<'de> serde::Deserialize<'de>>(token: &str) -> Result<T, AuthError> {
-    let config = config::get();
+pub fn decode_token<T: for<'de> serde::Deserialize<'de>>(
+    token: &str,
+    config: &Config
+) -> Result<T, AuthError> {
     let mut validation = jsonwebtoken::Validation::default();
     validation.leeway = config.jwt_validation_leeway_seconds as u64;
     let token_data = jsonwebtoken
         ::decode::<T>(token, &config.jwt_keys.decoding, &validation)
         .map_err(|e| {
-            tracing::error!("Invalid token: {}", token);
+            tracing::error!("Invalid token: {}", e);
             match e.kind() {
                 jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::ExpiredToken,
                 _ => AuthError::WrongCredentials,
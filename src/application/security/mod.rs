@@ -1,5 +1,8 @@
 pub mod auth_error;
+pub mod breached_passwords;
 pub mod jwt_auth;
 pub mod jwt_claims;
+pub mod one_time_token;
 pub mod roles;
 pub mod password;
+pub mod password_policy;
@@ -0,0 +1,15 @@
+use sha2::{ Digest, Sha256 };
+use uuid::Uuid;
+
+// The tokens sent to the users by mail, e.g. to reset the password or to verify the email,
+// only their hashes are stored so a leaked table does not reveal usable tokens.
+
+/// A random token of the 244 random bits of two v4 uuids
+pub fn generate() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+/// The SHA-256 hash of the token as stored
+pub fn hash(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
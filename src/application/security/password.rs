@@ -1,16 +1,103 @@
 use argon2::{
     password_hash::{ rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString },
+    Algorithm,
     Argon2,
+    KeyId,
+    Params,
+    ParamsBuilder,
+    Version,
 };
+use std::sync::Mutex;
 
-#[tracing::instrument(name = "Hashing user password", skip(password))]
-pub fn hash_password(password: &[u8]) -> String {
+use crate::application::config::Config;
+
+// the key id of the hashes made with the pepper, the hashes without it are verified without the pepper
+// and replaced on the next login, so the pepper can be introduced without resetting the passwords
+const PEPPER_KEY_ID: &[u8] = b"pepper";
+
+// verified in place of a missing or inactive user's hash, so every failed login costs the same,
+// hashed again when the parameters change
+static DUMMY_HASH: Mutex<Option<(Params, String)>> = Mutex::new(None);
+
+#[tracing::instrument(name = "Hashing user password", skip_all)]
+pub fn hash_password(password: &[u8], config: &Config) -> String {
     let salt = SaltString::generate(&mut OsRng);
-    Argon2::default().hash_password(password, &salt).expect("Unable to hash password.").to_string()
+    argon2(config, config.password_pepper.is_some())
+        .hash_password(password, &salt)
+        .expect("Unable to hash password.")
+        .to_string()
 }
 
-#[tracing::instrument(name = "Verifying user password", skip(password, hash))]
-pub fn verify_password(hash: &str, password: &[u8]) -> Result<(), argon2::password_hash::Error> {
+/// Verifies the password with the parameters of the hash,
+/// with the configured pepper if the hash was made with a pepper
+#[tracing::instrument(name = "Verifying user password", skip_all)]
+pub fn verify_password(
+    hash: &str,
+    password: &[u8],
+    config: &Config
+) -> Result<(), argon2::password_hash::Error> {
     let parsed_hash = PasswordHash::new(hash)?;
-    Argon2::default().verify_password(password, &parsed_hash)
+    let peppered = Params::try_from(&parsed_hash)?.keyid() == PEPPER_KEY_ID;
+    if peppered && config.password_pepper.is_none() {
+        tracing::error!("a password hashed with a pepper cannot be verified without PASSWORD_PEPPER");
+        return Err(argon2::password_hash::Error::Password);
+    }
+    argon2(config, peppered).verify_password(password, &parsed_hash)
+}
+
+/// Whether the hash is weaker than the configured parameters or lacks the configured pepper
+/// and should be replaced
+pub fn needs_rehash(hash: &str, config: &Config) -> bool {
+    let Ok(parsed_hash) = PasswordHash::new(hash) else {
+        return false;
+    };
+    let Ok(params) = Params::try_from(&parsed_hash) else {
+        return false;
+    };
+    let policy = params_of(config);
+    parsed_hash.algorithm != Algorithm::Argon2id.ident() ||
+        params.keyid() != policy.keyid() ||
+        parsed_hash.version != Some(Version::V0x13.into()) ||
+        params.m_cost() < policy.m_cost() ||
+        params.t_cost() < policy.t_cost() ||
+        params.p_cost() < policy.p_cost()
+}
+
+/// A hash of a random password with the configured parameters,
+/// verifying against it fails in about the same time as verifying against a user's hash
+pub fn dummy_hash(config: &Config) -> String {
+    let params = params_of(config);
+    let mut dummy_hash = DUMMY_HASH.lock().unwrap();
+    match dummy_hash.as_ref() {
+        Some((dummy_params, hash)) if *dummy_params == params => hash.clone(),
+        _ => {
+            let hash = hash_password(uuid::Uuid::new_v4().as_bytes(), config);
+            *dummy_hash = Some((params, hash.clone()));
+            hash
+        }
+    }
+}
+
+// the configured parameters, the verification takes the parameters of the hash
+fn argon2(config: &Config, peppered: bool) -> Argon2<'_> {
+    let params = params_of(config);
+    match &config.password_pepper {
+        Some(pepper) if peppered =>
+            Argon2::new_with_secret(pepper.as_bytes(), Algorithm::Argon2id, Version::V0x13, params).expect(
+                "PASSWORD_PEPPER is validated"
+            ),
+        _ => Argon2::new(Algorithm::Argon2id, Version::V0x13, params),
+    }
+}
+
+fn params_of(config: &Config) -> Params {
+    let mut params = ParamsBuilder::new();
+    params
+        .m_cost(config.password_hash_memory_kib)
+        .t_cost(config.password_hash_iterations)
+        .p_cost(config.password_hash_parallelism);
+    if config.password_pepper.is_some() {
+        params.keyid(KeyId::new(PEPPER_KEY_ID).expect("the key id is short enough"));
+    }
+    params.build().expect("PASSWORD_HASH_* parameters are validated")
 }
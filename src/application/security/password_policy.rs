@@ -0,0 +1,73 @@
+use crate::application::{ api_validation::FieldError, config::Config };
+
+use super::breached_passwords::BreachedPasswords;
+
+/// Checks a new password against the configured policy and the breached passwords,
+/// the problems are reported as errors of the `field`
+pub fn validate(
+    field: &str,
+    password: &str,
+    username: &str,
+    email: &str,
+    config: &Config,
+    breached_passwords: &BreachedPasswords
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    let length = password.chars().count();
+    if length < config.password_min_length {
+        errors.push(
+            FieldError::new(
+                field,
+                "too_short",
+                format!("must be at least {} characters long", config.password_min_length)
+            )
+        );
+    }
+    if length > config.password_max_length {
+        errors.push(
+            FieldError::new(
+                field,
+                "too_long",
+                format!("must be at most {} characters long", config.password_max_length)
+            )
+        );
+    }
+    if character_classes(password) < config.password_min_character_classes {
+        errors.push(
+            FieldError::new(
+                field,
+                "character_classes",
+                format!(
+                    "must contain at least {} of lowercase letters, uppercase letters, digits and symbols",
+                    config.password_min_character_classes
+                )
+            )
+        );
+    }
+    if password.eq_ignore_ascii_case(username) {
+        errors.push(FieldError::new(field, "same_as_username", "must not be the username"));
+    }
+    if password.eq_ignore_ascii_case(email) {
+        errors.push(FieldError::new(field, "same_as_email", "must not be the email"));
+    }
+    if breached_passwords.contains(password) {
+        errors.push(
+            FieldError::new(field, "breached", "has appeared in a data breach, choose another one")
+        );
+    }
+    errors
+}
+
+// lowercase letters, uppercase letters, digits and the other characters
+fn character_classes(password: &str) -> usize {
+    let classes: [fn(char) -> bool; 4] = [
+        char::is_lowercase,
+        char::is_uppercase,
+        |c| c.is_ascii_digit(),
+        |c| !c.is_alphanumeric(),
+    ];
+    classes
+        .iter()
+        .filter(|&&class| password.chars().any(class))
+        .count()
+}
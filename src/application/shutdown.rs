@@ -0,0 +1,100 @@
+use std::{ future::Future, sync::{ Arc, Mutex }, time::Duration };
+use tokio::{ sync::watch, task::JoinSet };
+
+/// Coordinates the graceful shutdown of the service:
+/// - the readiness probe reports 503 as soon as the shutdown is triggered
+/// - the listeners keep serving during the pre-stop delay, so the load balancer can stop routing to the instance
+/// - the listeners stop accepting and the in-flight requests are drained up to a deadline
+/// - the background jobs are stopped, the tasks of the requests completed up to a deadline
+///   and the connection pools closed
+#[derive(Clone)]
+pub struct Shutdown {
+    triggered: Arc<watch::Sender<bool>>,
+    jobs: Arc<Mutex<JoinSet<()>>>,
+    tasks: Arc<Mutex<JoinSet<()>>>,
+}
+
+impl Default for Shutdown {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        Self {
+            triggered: Arc::new(watch::Sender::new(false)),
+            jobs: Arc::new(Mutex::new(JoinSet::new())),
+            tasks: Arc::new(Mutex::new(JoinSet::new())),
+        }
+    }
+
+    /// Starts the shutdown, calling it again has no effect
+    pub fn trigger(&self) {
+        if !self.triggered.send_replace(true) {
+            tracing::info!("shutdown triggered, the service is not ready anymore");
+        }
+    }
+
+    pub fn is_triggered(&self) -> bool {
+        *self.triggered.borrow()
+    }
+
+    /// Completes when the shutdown is triggered
+    pub async fn triggered(&self) {
+        let mut triggered = self.triggered.subscribe();
+        let _ = triggered.wait_for(|triggered| *triggered).await;
+    }
+
+    /// Completes after the pre-stop delay following the trigger,
+    /// the listeners stop accepting connections at this point
+    pub async fn drain(&self, pre_stop_delay: Duration) {
+        self.triggered().await;
+        if !pre_stop_delay.is_zero() {
+            tracing::info!("waiting {:?} before draining the connections", pre_stop_delay);
+            tokio::time::sleep(pre_stop_delay).await;
+        }
+    }
+
+    /// Runs a background job until it completes or the shutdown is triggered
+    pub fn spawn_job(&self, name: &'static str, job: impl Future<Output = ()> + Send + 'static) {
+        let mut triggered = self.triggered.subscribe();
+        self.jobs.lock().unwrap().spawn(async move {
+            tokio::select! {
+                _ = job => {},
+                _ = triggered.wait_for(|triggered| *triggered) => {
+                    tracing::debug!("stopped the background job: {}", name);
+                }
+            }
+        });
+    }
+
+    /// Runs a task of a request in the background, e.g. sending a mail,
+    /// the shutdown waits for it to complete in `stop_jobs`
+    pub fn spawn_task(&self, name: &'static str, task: impl Future<Output = ()> + Send + 'static) {
+        let mut tasks = self.tasks.lock().unwrap();
+        // forget the completed tasks
+        while tasks.try_join_next().is_some() {}
+        tasks.spawn(async move {
+            task.await;
+            tracing::trace!("completed the background task: {}", name);
+        });
+    }
+
+    /// Triggers the shutdown if needed, waits for the background jobs to stop
+    /// and for the tasks to complete, the tasks still running after the timeout are aborted
+    pub async fn stop_jobs(&self, tasks_timeout: Duration) {
+        self.trigger();
+        let mut jobs = std::mem::take(&mut *self.jobs.lock().unwrap());
+        while jobs.join_next().await.is_some() {}
+
+        let mut tasks = std::mem::take(&mut *self.tasks.lock().unwrap());
+        let completed = tokio::time::timeout(tasks_timeout, async {
+            while tasks.join_next().await.is_some() {}
+        }).await;
+        if completed.is_err() {
+            tracing::warn!("aborted {} background tasks still running after {:?}", tasks.len(), tasks_timeout);
+            tasks.shutdown().await;
+        }
+    }
+}
@@ -1,10 +1,67 @@
 use sqlx::{Pool, Postgres};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 use tokio::sync::Mutex;
 
+use crate::infrastructure::{ captcha::{ self, Captcha }, mailer::{ self, Mailer } };
+
+use super::{
+    config::Config,
+    rate_limit_service::MemoryRateLimits,
+    security::breached_passwords::BreachedPasswords,
+    shutdown::Shutdown,
+};
+
 pub type SharedState = Arc<AppState>;
 
 pub struct AppState {
+    config: RwLock<Arc<Config>>,
     pub pgpool: Pool<Postgres>,
     pub redis: Mutex<redis::aio::Connection>,
+    pub shutdown: Shutdown,
+    pub rate_limits: MemoryRateLimits,
+    pub breached_passwords: BreachedPasswords,
+    pub mailer: Arc<dyn Mailer>,
+    pub captcha: Option<Arc<dyn Captcha>>,
+}
+
+impl AppState {
+    pub fn new(
+        config: Arc<Config>,
+        pgpool: Pool<Postgres>,
+        redis: redis::aio::Connection,
+        shutdown: Shutdown,
+        breached_passwords: BreachedPasswords
+    ) -> Self {
+        Self {
+            mailer: mailer::from_config(&config),
+            captcha: captcha::from_config(&config),
+            config: RwLock::new(config),
+            pgpool,
+            redis: Mutex::new(redis),
+            shutdown,
+            rate_limits: MemoryRateLimits::default(),
+            breached_passwords,
+        }
+    }
+
+    /// Replaces the mailer selected by the configuration, see `app::Plugins`
+    pub fn with_mailer(mut self, mailer: Arc<dyn Mailer>) -> Self {
+        self.mailer = mailer;
+        self
+    }
+
+    /// Replaces the CAPTCHA selected by the configuration, see `app::Plugins`
+    pub fn with_captcha(mut self, captcha: Arc<dyn Captcha>) -> Self {
+        self.captcha = Some(captcha);
+        self
+    }
+
+    /// A snapshot of the current configuration, it is swapped as a whole on reload
+    pub fn config(&self) -> Arc<Config> {
+        self.config.read().unwrap().clone()
+    }
+
+    pub fn set_config(&self, config: Arc<Config>) {
+        *self.config.write().unwrap() = config;
+    }
 }
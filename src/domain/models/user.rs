@@ -1,10 +1,11 @@
 use chrono::NaiveDateTime;
 use serde::{ Deserialize, Serialize };
 use sqlx::{ types::Uuid, FromRow };
+use utoipa::ToSchema;
 
 use crate::application::security;
 
-#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone)]
+#[derive(Debug, FromRow, Serialize, Deserialize, PartialEq, Clone, ToSchema)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
@@ -14,6 +15,9 @@ pub struct User {
     pub roles: String,
     pub created_at: Option<NaiveDateTime>,
     pub updated_at: Option<NaiveDateTime>,
+    /// cleared when the email changes, set by the verification
+    #[serde(default)]
+    pub email_verified_at: Option<NaiveDateTime>,
 }
 
 impl User {
@@ -0,0 +1,31 @@
+mod static_token;
+pub use static_token::StaticCaptcha;
+
+use std::{ future::Future, net::IpAddr, pin::Pin, sync::Arc };
+
+use crate::application::config::{ CaptchaKind, Config };
+
+#[derive(Debug, thiserror::Error)]
+pub enum CaptchaError {
+    #[error("captcha provider failed: {0}")]
+    Provider(String),
+}
+
+pub type VerifyFuture<'a> = Pin<Box<dyn Future<Output = Result<bool, CaptchaError>> + Send + 'a>>;
+
+/// Checks the CAPTCHA response of a client, e.g. with the verification API of the provider,
+/// pluggable with `app::Plugins`
+pub trait Captcha: Send + Sync {
+    fn verify<'a>(&'a self, response: &'a str, ip: Option<IpAddr>) -> VerifyFuture<'a>;
+}
+
+/// The CAPTCHA selected by `CAPTCHA_KIND`, `None` when disabled
+pub fn from_config(config: &Config) -> Option<Arc<dyn Captcha>> {
+    match config.captcha_kind {
+        CaptchaKind::Disabled => None,
+        CaptchaKind::Static =>
+            config.captcha_static_token
+                .as_ref()
+                .map(|token| Arc::new(StaticCaptcha::new(token)) as Arc<dyn Captcha>),
+    }
+}
@@ -0,0 +1,20 @@
+use std::net::IpAddr;
+
+use super::{ Captcha, VerifyFuture };
+
+/// Accepts only the configured response, for development and tests
+pub struct StaticCaptcha {
+    token: String,
+}
+
+impl StaticCaptcha {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self { token: token.into() }
+    }
+}
+
+impl Captcha for StaticCaptcha {
+    fn verify<'a>(&'a self, response: &'a str, _ip: Option<IpAddr>) -> VerifyFuture<'a> {
+        Box::pin(async move { Ok(response == self.token) })
+    }
+}
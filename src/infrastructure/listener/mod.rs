@@ -0,0 +1,67 @@
+mod serve;
+mod systemd;
+#[cfg(unix)]
+mod unix;
+
+pub use serve::{ serve, Accept };
+#[cfg(unix)]
+pub use unix::UnixSocket;
+
+use std::{ fmt, io, net::SocketAddr };
+use tokio::net::TcpListener;
+
+use crate::application::config::Config;
+
+/// The socket the service accepts the connections on
+pub enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)] Unix(UnixSocket),
+}
+
+impl Listener {
+    /// The local address of a TCP listener
+    pub fn tcp_addr(&self) -> Option<SocketAddr> {
+        match self {
+            Self::Tcp(listener) => listener.local_addr().ok(),
+            #[cfg(unix)]
+            Self::Unix(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Listener {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tcp(listener) =>
+                match listener.local_addr() {
+                    Ok(addr) => write!(f, "{}", addr),
+                    Err(_) => write!(f, "tcp socket"),
+                }
+            #[cfg(unix)]
+            Self::Unix(socket) => write!(f, "{}", socket),
+        }
+    }
+}
+
+/// Binds the listener of the service, in order of preference:
+/// the socket passed by systemd (`LISTEN_FDS`), the `SERVICE_UNIX_SOCKET` or the `SERVICE_HOST:SERVICE_PORT`
+pub async fn bind(config: &Config) -> io::Result<Listener> {
+    if let Some(listener) = systemd::inherited()? {
+        tracing::info!("using the socket passed by systemd");
+        // the configuration validation only knows the `SERVICE_UNIX_SOCKET`
+        #[cfg(unix)]
+        if matches!(listener, Listener::Unix(_)) && config.tls_enabled() {
+            return Err(
+                io::Error::new(io::ErrorKind::InvalidInput, "TLS is not supported on a Unix socket")
+            );
+        }
+        return Ok(listener);
+    }
+
+    #[cfg(unix)]
+    if let Some(path) = &config.service_unix_socket {
+        return unix::UnixSocket::bind(path, config.service_unix_socket_mode).map(Listener::Unix);
+    }
+
+    TcpListener::bind(config.service_socket_addr()).await.map(Listener::Tcp)
+}
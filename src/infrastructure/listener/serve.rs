@@ -0,0 +1,86 @@
+use axum::{ body::Body, extract::{ ConnectInfo, Request }, response::Response };
+use hyper::body::Incoming;
+use hyper_util::{
+    rt::{ TokioExecutor, TokioIo },
+    server::{ conn::auto::Builder, graceful::GracefulShutdown },
+    service::TowerToHyperService,
+};
+use std::{ convert::Infallible, future::Future, io, net::SocketAddr, time::Duration };
+use tokio::{ io::{ AsyncRead, AsyncWrite }, net::{ TcpListener, TcpStream }, task::JoinSet };
+use tower::{ Service, ServiceExt };
+
+/// A listener the connections are accepted from, with the address of the peer if known
+pub trait Accept: Send {
+    type Stream: AsyncRead + AsyncWrite + Unpin + Send + 'static;
+
+    fn accept(&self) -> impl Future<Output = io::Result<(Self::Stream, Option<SocketAddr>)>> + Send;
+}
+
+impl Accept for TcpListener {
+    type Stream = TcpStream;
+
+    async fn accept(&self) -> io::Result<(Self::Stream, Option<SocketAddr>)> {
+        let (stream, addr) = TcpListener::accept(self).await?;
+        Ok((stream, Some(addr)))
+    }
+}
+
+/// Serves the app until the `drain` future completes, then stops accepting connections
+/// and waits for the in-flight requests to complete up to the `drain_timeout`,
+/// the remaining connections are closed after the timeout
+pub async fn serve<L, S>(listener: L, app: S, drain: impl Future<Output = ()>, drain_timeout: Duration)
+    where
+        L: Accept,
+        S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+        S::Future: Send
+{
+    let builder = Builder::new(TokioExecutor::new());
+    let graceful = GracefulShutdown::new();
+    let mut connections = JoinSet::new();
+    tokio::pin!(drain);
+
+    loop {
+        let (stream, peer_addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    // e.g. too many open files, retry later the same way `axum::serve` does
+                    tracing::error!("accept error: {}", e);
+                    tokio::time::sleep(Duration::from_secs(1)).await;
+                    continue;
+                }
+            },
+            // forget the completed connections
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            _ = &mut drain => break,
+        };
+
+        // the hyper body is converted and the peer address provided the same way `axum::serve` does
+        let service = app.clone().map_request(move |mut request: Request<Incoming>| {
+            if let Some(peer_addr) = peer_addr {
+                request.extensions_mut().insert(ConnectInfo(peer_addr));
+            }
+            request.map(Body::new)
+        });
+        let connection = builder
+            .serve_connection_with_upgrades(TokioIo::new(stream), TowerToHyperService::new(service))
+            .into_owned();
+        let connection = graceful.watch(connection);
+        connections.spawn(async move {
+            if let Err(e) = connection.await {
+                tracing::debug!("connection error: {}", e);
+            }
+        });
+    }
+
+    // stop accepting, the idle connections are closed and the busy ones after the in-flight requests
+    drop(listener);
+    tracing::info!("draining {} connections", connections.len());
+    match tokio::time::timeout(drain_timeout, graceful.shutdown()).await {
+        Ok(()) => tracing::info!("all connections drained"),
+        Err(_) => {
+            tracing::warn!("drain timeout exceeded, closing {} connections", connections.len());
+            connections.shutdown().await;
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use listenfd::ListenFd;
+use std::io;
+use tokio::net::TcpListener;
+
+use super::Listener;
+
+/// Takes the first socket passed with the systemd socket activation protocol,
+/// the listening socket outlives the service process, so the restarts do not refuse connections
+pub fn inherited() -> io::Result<Option<Listener>> {
+    let mut fds = ListenFd::from_env();
+    if fds.len() == 0 {
+        return Ok(None);
+    }
+    if fds.len() > 1 {
+        tracing::warn!("{} sockets passed by systemd, only the first one is used", fds.len());
+    }
+
+    let tcp_error = match fds.take_tcp_listener(0) {
+        Ok(Some(listener)) => {
+            listener.set_nonblocking(true)?;
+            return TcpListener::from_std(listener).map(|listener| Some(Listener::Tcp(listener)));
+        }
+        Ok(None) => return Ok(None),
+        Err(e) => e,
+    };
+
+    #[cfg(unix)]
+    if let Some(listener) = fds.take_unix_listener(0).ok().flatten() {
+        listener.set_nonblocking(true)?;
+        return tokio::net::UnixListener
+            ::from_std(listener)
+            .map(|listener| Some(Listener::Unix(super::UnixSocket::inherited(listener))));
+    }
+
+    Err(tcp_error)
+}
@@ -0,0 +1,61 @@
+use std::{ fmt, fs, io, net::SocketAddr, os::unix::fs::{ FileTypeExt, PermissionsExt }, path::PathBuf };
+use tokio::net::{ UnixListener, UnixStream };
+
+use super::Accept;
+use crate::application::config::FileMode;
+
+/// A Unix domain socket listener,
+/// the socket file is removed on drop unless the socket was passed by systemd
+pub struct UnixSocket {
+    listener: UnixListener,
+    path: Option<PathBuf>,
+}
+
+impl UnixSocket {
+    /// Binds the socket at the path and sets the permissions of the socket file,
+    /// a socket file left by a previous run is replaced
+    pub fn bind(path: &str, mode: FileMode) -> io::Result<Self> {
+        let path = PathBuf::from(path);
+        if fs::symlink_metadata(&path).is_ok_and(|metadata| metadata.file_type().is_socket()) {
+            fs::remove_file(&path)?;
+        }
+        let listener = UnixListener::bind(&path)?;
+        let socket = Self {
+            listener,
+            path: Some(path),
+        };
+        fs::set_permissions(socket.path.as_ref().unwrap(), fs::Permissions::from_mode(mode.0))?;
+        Ok(socket)
+    }
+
+    pub fn inherited(listener: UnixListener) -> Self {
+        Self { listener, path: None }
+    }
+}
+
+impl Accept for UnixSocket {
+    type Stream = UnixStream;
+
+    async fn accept(&self) -> io::Result<(Self::Stream, Option<SocketAddr>)> {
+        let (stream, _) = self.listener.accept().await?;
+        Ok((stream, None))
+    }
+}
+
+// the socket file is removed when the listener is dropped, before the connections are drained
+impl Drop for UnixSocket {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
+
+impl fmt::Display for UnixSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.listener.local_addr().ok().and_then(|addr| addr.as_pathname().map(PathBuf::from)) {
+            Some(path) => write!(f, "unix:{}", path.display()),
+            None => write!(f, "unix socket"),
+        }
+    }
+}
@@ -0,0 +1,35 @@
+use std::path::PathBuf;
+use uuid::Uuid;
+
+use super::{ Mail, Mailer, SendFuture };
+
+/// Writes each message to a new `.eml` file of the directory, created when missing
+pub struct FileMailer {
+    dir: PathBuf,
+}
+
+impl FileMailer {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+}
+
+impl Mailer for FileMailer {
+    fn send<'a>(&'a self, mail: &'a Mail) -> SendFuture<'a> {
+        Box::pin(async move {
+            tokio::fs::create_dir_all(&self.dir).await?;
+            // ordered by the time of sending
+            let name = format!("{}-{}.eml", chrono::Utc::now().format("%Y%m%dT%H%M%S%.6f"), Uuid::new_v4());
+            let contents = format!(
+                "From: {}\r\nTo: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+                mail.from,
+                mail.to,
+                mail.subject,
+                mail.body
+            );
+            tokio::fs::write(self.dir.join(name), contents).await?;
+            tracing::debug!("mail written to {}", self.dir.display());
+            Ok(())
+        })
+    }
+}
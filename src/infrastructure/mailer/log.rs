@@ -0,0 +1,38 @@
+use super::{ Mail, Mailer, SendFuture };
+
+// the one-time tokens are 64 hexadecimal digits, shorter runs are kept
+const TOKEN_MIN_LENGTH: usize = 32;
+
+/// Writes the messages to the log, the addresses are redacted like in any other log line.
+/// The body is only logged at debug level, with the tokens redacted, so the log does not hold live tokens.
+pub struct LogMailer;
+
+impl Mailer for LogMailer {
+    fn send<'a>(&'a self, mail: &'a Mail) -> SendFuture<'a> {
+        Box::pin(async move {
+            tracing::info!("mail from: {}, to: {}, subject: {}", mail.from, mail.to, mail.subject);
+            tracing::debug!("mail body:\n{}", redact_tokens(&mail.body));
+            Ok(())
+        })
+    }
+}
+
+fn redact_tokens(body: &str) -> String {
+    let mut redacted = String::with_capacity(body.len());
+    let mut run = String::new();
+    for c in body.chars().chain(std::iter::once('\n')) {
+        if c.is_ascii_hexdigit() {
+            run.push(c);
+            continue;
+        }
+        if run.len() >= TOKEN_MIN_LENGTH {
+            redacted.push_str("[redacted]");
+        } else {
+            redacted.push_str(&run);
+        }
+        run.clear();
+        redacted.push(c);
+    }
+    redacted.pop();
+    redacted
+}
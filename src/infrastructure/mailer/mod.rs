@@ -0,0 +1,39 @@
+mod file;
+mod log;
+pub use file::FileMailer;
+pub use log::LogMailer;
+
+use std::{ future::Future, pin::Pin, sync::Arc };
+
+use crate::application::config::{ Config, MailerKind };
+
+/// A plain text message
+#[derive(Debug, Clone)]
+pub struct Mail {
+    pub from: String,
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MailerError {
+    #[error("could not write the message: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+pub type SendFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailerError>> + Send + 'a>>;
+
+/// Delivers the messages, implemented for the development mailers
+/// and pluggable with `app::Plugins`
+pub trait Mailer: Send + Sync {
+    fn send<'a>(&'a self, mail: &'a Mail) -> SendFuture<'a>;
+}
+
+/// The mailer selected by `MAILER_KIND`
+pub fn from_config(config: &Config) -> Arc<dyn Mailer> {
+    match config.mailer_kind {
+        MailerKind::Log => Arc::new(LogMailer),
+        MailerKind::File => Arc::new(FileMailer::new(&config.mailer_file_dir)),
+    }
+}
@@ -1,2 +1,7 @@
+pub mod captcha;
+pub mod listener;
+pub mod mailer;
 pub mod postgres;
 pub mod redis;
+pub mod telemetry;
+pub mod tls;
@@ -0,0 +1,10 @@
+-- create password reset tokens table, only the SHA-256 hashes of the tokens are stored
+CREATE TABLE password_reset_tokens (
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    user_id UUID NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL
+);
+
+CREATE INDEX password_reset_tokens_user_id_idx ON password_reset_tokens (user_id);
@@ -0,0 +1,10 @@
+-- create registration invites table, only the SHA-256 hashes of the invite codes are stored
+CREATE TABLE registration_invites (
+    token_hash TEXT PRIMARY KEY NOT NULL,
+    -- the invite is only valid for this email when set
+    email TEXT,
+    created_by UUID NOT NULL,
+    expires_at TIMESTAMP NOT NULL,
+    used_at TIMESTAMP,
+    created_at TIMESTAMP NOT NULL
+);
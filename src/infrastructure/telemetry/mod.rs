@@ -0,0 +1,6 @@
+mod logging;
+mod otlp;
+mod propagation;
+pub use logging::{ filter_layer, fmt_layer, redact, reload_filter, RedactingMakeWriter, REDACTED };
+pub use otlp::tracer_provider;
+pub use propagation::set_parent_from_headers;
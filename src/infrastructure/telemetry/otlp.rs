@@ -0,0 +1,36 @@
+use opentelemetry::{ global, KeyValue };
+use opentelemetry_otlp::{ SpanExporter, WithExportConfig };
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    runtime,
+    trace::TracerProvider,
+    Resource,
+};
+
+use crate::application::config::Config;
+
+// builds the OTLP (gRPC) tracer provider if an exporter endpoint is configured
+pub fn tracer_provider(config: &Config) -> Option<TracerProvider> {
+    let endpoint = config.otel_exporter_otlp_endpoint.as_ref()?;
+
+    let exporter = match SpanExporter::builder().with_tonic().with_endpoint(endpoint).build() {
+        Ok(exporter) => exporter,
+        Err(e) => {
+            eprintln!("Could not build the OTLP exporter: {}", e);
+            return None;
+        }
+    };
+
+    let provider = TracerProvider::builder()
+        .with_batch_exporter(exporter, runtime::Tokio)
+        .with_resource(
+            Resource::new(vec![KeyValue::new("service.name", config.otel_service_name.clone())])
+        )
+        .build();
+
+    // W3C trace context: `traceparent` and `tracestate` headers
+    global::set_text_map_propagator(TraceContextPropagator::new());
+    global::set_tracer_provider(provider.clone());
+
+    Some(provider)
+}
@@ -0,0 +1,26 @@
+use axum::http::HeaderMap;
+use opentelemetry::{ global, propagation::Extractor };
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+struct HeaderExtractor<'a>(&'a HeaderMap);
+
+impl Extractor for HeaderExtractor<'_> {
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).and_then(|value| value.to_str().ok())
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        self.0
+            .keys()
+            .map(|key| key.as_str())
+            .collect()
+    }
+}
+
+// continues the remote trace of the caller (`traceparent`, `tracestate`) in the given span
+pub fn set_parent_from_headers(span: &tracing::Span, headers: &HeaderMap) {
+    let context = global::get_text_map_propagator(|propagator| {
+        propagator.extract(&HeaderExtractor(headers))
+    });
+    span.set_parent(context);
+}
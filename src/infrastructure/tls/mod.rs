@@ -0,0 +1,2 @@
+mod server_config;
+pub use server_config::{ rustls_config, watch };
@@ -0,0 +1,113 @@
+use axum_server::tls_rustls::RustlsConfig;
+use rustls::{
+    crypto::ring,
+    pki_types::{ CertificateDer, PrivateKeyDer },
+    server::WebPkiClientVerifier,
+    RootCertStore,
+    ServerConfig,
+};
+use std::{ fs::File, io::BufReader, sync::Arc, time::{ Duration, SystemTime } };
+
+use crate::application::config::Config;
+
+/// Builds the rustls configuration if TLS is enabled
+pub fn rustls_config(config: &Config) -> Result<Option<RustlsConfig>, String> {
+    if !config.tls_enabled() {
+        return Ok(None);
+    }
+    let server_config = server_config(config)?;
+    tracing::info!("TLS certificates loaded");
+    Ok(Some(RustlsConfig::from_config(Arc::new(server_config))))
+}
+
+/// Reloads the certificates when their files are modified,
+/// the current certificates are kept if the new ones cannot be loaded
+pub async fn watch(rustls_config: RustlsConfig, config: Arc<Config>) {
+    if config.tls_reload_interval_seconds == 0 {
+        return;
+    }
+    let mut interval = tokio::time::interval(
+        Duration::from_secs(config.tls_reload_interval_seconds)
+    );
+    let mut modified = files_modified(&config);
+    loop {
+        interval.tick().await;
+        let current = files_modified(&config);
+        if current == modified {
+            continue;
+        }
+        modified = current;
+        match server_config(&config) {
+            Ok(server_config) => {
+                rustls_config.reload_from_config(Arc::new(server_config));
+                tracing::info!("TLS certificates reloaded");
+            }
+            Err(e) => tracing::error!("TLS certificates reload rejected, {}", e),
+        }
+    }
+}
+
+fn server_config(config: &Config) -> Result<ServerConfig, String> {
+    let certs = load_certs(config.tls_cert_path.as_deref().unwrap_or_default())?;
+    let key = load_key(config.tls_key_path.as_deref().unwrap_or_default())?;
+
+    let provider = Arc::new(ring::default_provider());
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+
+    // mutual TLS, the client certificates are verified against the configured CA
+    let builder = match &config.tls_client_ca_path {
+        Some(ca_path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certs(ca_path)? {
+                roots.add(cert).map_err(|e| format!("{}: {}", ca_path, e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider);
+            let verifier = if config.tls_client_auth_required {
+                verifier
+            } else {
+                verifier.allow_unauthenticated()
+            };
+            builder.with_client_cert_verifier(verifier.build().map_err(|e| e.to_string())?)
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut server_config = builder.with_single_cert(certs, key).map_err(|e| e.to_string())?;
+    server_config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    Ok(server_config)
+}
+
+fn load_certs(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    let certs = rustls_pemfile
+        ::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("{}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("{}: no certificates found", path));
+    }
+    Ok(certs)
+}
+
+fn load_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("{}: {}", path, e))?;
+    rustls_pemfile
+        ::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("{}: {}", path, e))?
+        .ok_or_else(|| format!("{}: no private key found", path))
+}
+
+fn files_modified(config: &Config) -> Vec<Option<SystemTime>> {
+    [&config.tls_cert_path, &config.tls_key_path, &config.tls_client_ca_path]
+        .into_iter()
+        .flatten()
+        .map(|path| {
+            std::fs
+                ::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
@@ -1,23 +1,35 @@
+use axum_web::{ application::{ app_const::SERVICE_NAME, config }, infrastructure::telemetry };
+use opentelemetry::trace::TracerProvider;
 use tokio::sync::oneshot;
 use tracing_subscriber::{ layer::SubscriberExt, util::SubscriberInitExt };
 
 #[tokio::main]
 async fn main() {
+    // load configuration
+    config::load();
+
     // tracing configuration
-    let filter_layer = tracing_subscriber::EnvFilter
-        ::try_from_default_env()
-        .unwrap_or_else(|_| "axum_web=trace".into());
-    let fmt_layer = tracing_subscriber::fmt
-        ::layer()
-        .compact()
-        .with_target(false)
-        .with_file(true)
-        .with_line_number(true);
-    tracing_subscriber::registry().with(filter_layer).with(fmt_layer).init();
+    let filter_layer = telemetry::filter_layer(&config::get().log_filter);
+    let fmt_layer = telemetry::fmt_layer(config::get().log_format, std::io::stdout);
+
+    // export traces to an OpenTelemetry collector if configured
+    let tracer_provider = telemetry::tracer_provider(&config::get());
+    let otel_layer = tracer_provider
+        .as_ref()
+        .map(|provider| tracing_opentelemetry::layer().with_tracer(provider.tracer(SERVICE_NAME)));
+
+    tracing_subscriber::registry().with(filter_layer).with(fmt_layer).with(otel_layer).init();
 
     tracing::info!("{} v{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
 
     let (api_ready_tx, api_ready_rx) = oneshot::channel();
     axum_web::application::app::start_server(api_ready_tx).await;
     api_ready_rx.await.expect("Could not start server");
+
+    // flush the pending spans
+    if let Some(provider) = tracer_provider {
+        if let Err(e) = provider.shutdown() {
+            eprintln!("Could not shutdown the tracer provider: {}", e);
+        }
+    }
 }
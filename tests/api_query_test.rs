@@ -0,0 +1,85 @@
+use axum::{ routing::{ get, post }, Router };
+use axum_web::application::{ api_json::Json, api_query::{ Form, Query } };
+use reqwest::StatusCode;
+use serde::{ Deserialize, Serialize };
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Filter {
+    active: bool,
+    limit: u32,
+}
+
+async fn start_router() -> String {
+    let app = Router::new()
+        .route(
+            "/query",
+            get(|Query(filter): Query<Filter>| async move { Json(filter) })
+        )
+        .route(
+            "/form",
+            post(|Form(filter): Form<Filter>| async move { Json(filter) })
+        );
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+    format!("http://{}", addr)
+}
+
+#[tokio::test]
+async fn query_extractor_test() {
+    let base_url = start_router().await;
+
+    let response = reqwest::get(format!("{}/query?active=true&limit=10", base_url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["active"], true);
+    assert_eq!(json["limit"], 10);
+
+    let response = reqwest::get(format!("{}/query?active=yes&limit=10", base_url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["code"], "query.invalid_query_string");
+}
+
+#[tokio::test]
+async fn form_extractor_test() {
+    let base_url = start_router().await;
+    let url = format!("{}/form", base_url);
+
+    let response = reqwest::Client
+        ::new()
+        .post(&url)
+        .header("Content-type", "application/x-www-form-urlencoded")
+        .body("active=false&limit=5")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["active"], false);
+    assert_eq!(json["limit"], 5);
+
+    let response = reqwest::Client
+        ::new()
+        .post(&url)
+        .header("Content-type", "application/json")
+        .body("{}")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["code"], "form.invalid_content_type");
+
+    let response = reqwest::Client
+        ::new()
+        .post(&url)
+        .header("Content-type", "application/x-www-form-urlencoded")
+        .body("active=false")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["code"], "form.invalid_body");
+}
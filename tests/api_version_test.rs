@@ -0,0 +1,63 @@
+use axum_web::application::{ app_const::*, config };
+use reqwest::StatusCode;
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+#[tokio::test]
+#[serial]
+async fn accept_version_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+    let config = config::get();
+
+    // assert that the api version negotiation is enabled
+    assert!(config.api_version_negotiation);
+
+    let url = format!("{}/heartbeat/1", config.service_http_addr());
+
+    // unversioned path without the Accept-Version header
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // unversioned path routed by the Accept-Version header
+    for accept_version in ["v1", "1"] {
+        let response = reqwest::Client
+            ::new()
+            .get(url.as_str())
+            .header("Accept-Version", accept_version)
+            .send().await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get("deprecation").is_none());
+        let json: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(json["service"], SERVICE_NAME);
+        assert_eq!(json["heartbeat-id"], "1");
+    }
+
+    // the unversioned routes are not rewritten
+    for path in ["health/live", "openapi.json", "metrics"] {
+        let response = reqwest::Client
+            ::new()
+            .get(format!("{}/{}", config.service_http_addr(), path))
+            .header("Accept-Version", "v1")
+            .send().await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", path);
+    }
+
+    // unsupported version
+    let response = reqwest::Client
+        ::new()
+        .get(url.as_str())
+        .header("Accept-Version", "v0")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["detail"], "Unsupported API version: v0");
+    assert_eq!(json["instance"], "/heartbeat/1");
+    assert_eq!(json["request_id"], request_id);
+}
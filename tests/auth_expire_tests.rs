@@ -15,7 +15,7 @@ async fn access_token_expire_test() {
     // assert that revoked options are enabled
     assert!(config.jwt_enable_revoked_tokens);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -27,9 +27,9 @@ async fn access_token_expire_test() {
     ))
     .await;
 
-    // check the access to the root handler with expired token
+    // check the access to the profile handler with expired token
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
@@ -38,9 +38,9 @@ async fn access_token_expire_test() {
     assert_eq!(status, StatusCode::OK);
     let (access_token_new, _) = result.unwrap();
 
-    // try access to the root handler with new token
+    // try access to the profile handler with new token
     assert_eq!(
-        route::fetch_root(&access_token_new).await.unwrap(),
+        route::fetch_profile(&access_token_new).await.unwrap(),
         StatusCode::OK
     );
 }
@@ -56,7 +56,7 @@ async fn refresh_token_expire_test() {
     assert!(config.jwt_enable_revoked_tokens);
 
     // login
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -10,19 +10,19 @@ async fn login_test() {
     // load the test configuration and start the api server
     utils::start_api().await;
 
-    // try unauthorized access to the root handler
+    // try unauthorized access to the profile handler
     assert_eq!(
-        route::fetch_root("").await.unwrap(),
+        route::fetch_profile("").await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
     let username_wrong = format!("{}1", TEST_ADMIN_USERNAME);
-    let (status, _) = auth::login(&username_wrong, TEST_ADMIN_PASSWORD_HASH)
+    let (status, _) = auth::login(&username_wrong, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let password_wrong = format!("{}1", TEST_ADMIN_PASSWORD_HASH);
+    let password_wrong = format!("{}1", TEST_ADMIN_PASSWORD);
     let (status, _) = auth::login(TEST_ADMIN_USERNAME, &password_wrong)
         .await
         .unwrap();
@@ -31,15 +31,15 @@ async fn login_test() {
     let (status, _) = auth::login(&username_wrong, &password_wrong).await.unwrap();
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
     let (access_token, _) = result.unwrap();
 
-    // access to the root handler
+    // access to the profile handler
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::OK
     );
 }
@@ -15,30 +15,30 @@ async fn logout_test() {
     // assert that revoked options are enabled
     assert!(config.jwt_enable_revoked_tokens);
 
-    // try unauthorized access to the root handler
+    // try unauthorized access to the profile handler
     assert_eq!(
-        route::fetch_root("").await.unwrap(),
+        route::fetch_profile("").await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
     let (access_token, refresh_token) = result.unwrap();
 
-    // access to the root handler
+    // access to the profile handler
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::OK
     );
 
     // logout
     assert_eq!(auth::logout(&refresh_token).await.unwrap(), StatusCode::OK);
 
-    // try access to the root handler after logout
+    // try access to the profile handler after logout
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 }
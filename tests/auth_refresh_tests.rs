@@ -11,7 +11,7 @@ async fn refresh_test() {
     // load the test configuration and start the api server
     utils::start_api().await;
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -25,15 +25,15 @@ async fn refresh_test() {
     assert_ne!(access_token, access_token_new);
     assert_ne!(refresh_token, refresh_token_new);
 
-    // try access to the root handler with old token
+    // try access to the profile handler with old token
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
-    // try access to the root handler with new token
+    // try access to the profile handler with new token
     assert_eq!(
-        route::fetch_root(&access_token_new).await.unwrap(),
+        route::fetch_profile(&access_token_new).await.unwrap(),
         StatusCode::OK
     );
 }
@@ -48,7 +48,7 @@ async fn refresh_logout_test() {
     // assert that revoked options are enabled
     assert!(config.jwt_enable_revoked_tokens);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
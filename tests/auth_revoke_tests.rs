@@ -18,13 +18,13 @@ async fn revoke_user_test() {
     // assert that revoked options are enabled
     assert!(config.jwt_enable_revoked_tokens);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
     let (access_token, _) = result.unwrap();
 
-    let access_claims: AccessClaims = jwt_claims::decode_token(&access_token).unwrap();
+    let access_claims: AccessClaims = jwt_claims::decode_token(&access_token, &config).unwrap();
     let user_id = access_claims.sub;
 
     assert_eq!(
@@ -32,9 +32,9 @@ async fn revoke_user_test() {
         StatusCode::OK
     );
 
-    // try access to the root handler with the same token again
+    // try access to the profile handler with the same token again
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
@@ -52,7 +52,7 @@ async fn revoke_all_test() {
     // assert that revoked options are enabled
     assert!(config.jwt_enable_revoked_tokens);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -60,9 +60,9 @@ async fn revoke_all_test() {
 
     auth::revoke_all(&access_token).await.unwrap();
 
-    // try access to the root handler with the same token again
+    // try access to the profile handler with the same token again
     assert_eq!(
-        route::fetch_root(&access_token).await.unwrap(),
+        route::fetch_profile(&access_token).await.unwrap(),
         StatusCode::UNAUTHORIZED
     );
 
@@ -81,7 +81,7 @@ async fn cleanup_test() {
     assert!(config.jwt_enable_revoked_tokens);
 
     // login
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -107,7 +107,7 @@ async fn cleanup_test() {
     ))
     .await;
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -7,13 +7,13 @@ const API_V1: &str = "v1";
 
 pub async fn login(
     username: &str,
-    password_hash: &str,
+    password: &str,
 ) -> GenericResult<(reqwest::StatusCode, Option<(String, String)>)> {
     let url = utils::build_url(API_V1, PATH_AUTH, "login");
 
     let params = format!(
-        "{{\"username\":\"{}\", \"password_hash\":\"{}\"}}",
-        username, password_hash
+        "{{\"username\":\"{}\", \"password\":\"{}\"}}",
+        username, password
     );
 
     let response = reqwest::Client::new()
@@ -1,5 +1,5 @@
 pub const TEST_ADMIN_USERNAME: &str = "admin";
-pub const TEST_ADMIN_PASSWORD_HASH: &str = "7c44575b741f02d49c3e988ba7aa95a8fb6d90c0ef63a97236fa54bfcfbd9d51";
+pub const TEST_ADMIN_PASSWORD: &str = "123";
 
 type GenericResult<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
@@ -1,22 +1,25 @@
-use axum_web::application::config;
+use crate::common::utils;
 
 use super::GenericResult;
 
-pub async fn fetch_root(access_token: &str) -> GenericResult<reqwest::StatusCode> {
-    let url = config::get().service_http_addr();
+const PATH_USERS: &str = "users";
+const API_V1: &str = "v1";
+
+// the own profile is the route open to any authenticated user
+pub async fn fetch_profile(access_token: &str) -> GenericResult<reqwest::StatusCode> {
+    let url = utils::build_url(API_V1, PATH_USERS, "me");
 
     let authorization = format!("Bearer {}", access_token);
     let response = reqwest::Client::new()
-        .get(&url)
+        .get(url.as_str())
         .header("Authorization", authorization)
         .send()
         .await?;
 
     let response_status = response.status();
     if response_status == reqwest::StatusCode::OK {
-        let found = response.text().await.unwrap();
-        let expected = r#"{"message":"Hello from Axum-Web!"}"#;
-        assert_eq!(found, expected);
+        let json: serde_json::Value = response.json().await.unwrap();
+        assert!(json["id"].is_string());
     }
     Ok(response_status)
 }
@@ -1,11 +1,19 @@
-use axum_web::application::config;
+use axum_web::application::{ app::{ self, Plugins }, config::{ self, Config }, shutdown::Shutdown };
+use std::sync::{ Arc, Mutex, PoisonError };
 use std::time::Duration;
 use tokio::sync::oneshot;
 use tokio::time::{timeout_at, Instant};
 
+// the environment is shared by the tests of a binary,
+// the variables are set and the configuration is loaded under this lock
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
 pub async fn start_api() {
-    std::env::set_var("ENV_TEST", "1");
-    config::load();
+    {
+        let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+        std::env::set_var("ENV_TEST", "1");
+        config::load();
+    }
 
     let (api_ready_tx, api_ready_rx) = oneshot::channel();
 
@@ -35,3 +43,49 @@ pub fn build_path(version: &str, path: &str) -> reqwest::Url {
     let url = format!("{}/{}/{}", config::get().service_http_addr(), version, path);
     reqwest::Url::parse(&url).unwrap()
 }
+
+/// Loads the test configuration overridden by the variables, the service listens on a free port
+pub fn config_with(vars: &[(&str, &str)]) -> Config {
+    let _env = ENV_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    std::env::set_var("ENV_TEST", "1");
+    for (key, value) in vars {
+        std::env::set_var(key, value);
+    }
+    let config = config::try_load();
+    for (key, _) in vars {
+        std::env::remove_var(key);
+    }
+    let mut config = config.unwrap();
+    config.service_port = free_port();
+    config
+}
+
+/// Starts a server with the test configuration overridden by the variables,
+/// fails if the server could not be started
+pub async fn try_start_api_with(vars: &[(&str, &str)]) -> Result<Arc<Config>, oneshot::error::RecvError> {
+    try_start_api_with_plugins(vars, Plugins::default()).await
+}
+
+/// Starts a server like `try_start_api_with`, with the given implementations plugged in
+pub async fn try_start_api_with_plugins(
+    vars: &[(&str, &str)],
+    plugins: Plugins
+) -> Result<Arc<Config>, oneshot::error::RecvError> {
+    let config = Arc::new(config_with(vars));
+    let (api_ready_tx, api_ready_rx) = oneshot::channel();
+    tokio::spawn(app::start_server_with_plugins(config.clone(), Shutdown::new(), plugins, api_ready_tx));
+    tokio::time::timeout(Duration::from_secs(5), api_ready_rx)
+        .await
+        .expect("Could not start API Service in 5 seconds")?;
+    Ok(config)
+}
+
+/// Starts a server with the test configuration overridden by the variables
+pub async fn start_api_with(vars: &[(&str, &str)]) -> Arc<Config> {
+    try_start_api_with(vars).await.unwrap()
+}
+
+// a port free when the configuration is loaded, bound by the server right after
+fn free_port() -> u16 {
+    std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+}
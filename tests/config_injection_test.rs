@@ -0,0 +1,64 @@
+use axum_web::{
+    application::{
+        config::{ self, Config },
+        security::{ auth_error::AuthError, jwt_auth, jwt_claims::{ self, AccessClaims } },
+    },
+    domain::models::user::User,
+};
+use uuid::Uuid;
+
+fn config_with(secret: &str, expire_access_token_seconds: i64) -> Config {
+    config
+        ::from_toml(
+            &format!(
+                r#"
+                [postgres]
+                user = "admin"
+                password = "pswd1234"
+                db = "axum_web"
+
+                [jwt]
+                secret = "{secret}"
+                expire_access_token_seconds = {expire_access_token_seconds}
+                "#
+            )
+        )
+        .unwrap()
+}
+
+fn user() -> User {
+    User {
+        id: Uuid::new_v4(),
+        username: "alice".to_string(),
+        email: "alice@example.com".to_string(),
+        password: String::new(),
+        active: true,
+        roles: "user".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    }
+}
+
+#[test]
+fn config_injection_test() {
+    // two configurations side by side in the same process
+    let short = config_with("0123456789abcdef0123456789abcdef", 60);
+    let long = config_with("fedcba9876543210fedcba9876543210", 600);
+
+    let short_tokens = jwt_auth::generate_tokens(user(), &short);
+    let long_tokens = jwt_auth::generate_tokens(user(), &long);
+
+    let short_claims: AccessClaims = jwt_claims
+        ::decode_token(&short_tokens.access_token, &short)
+        .unwrap();
+    let long_claims: AccessClaims = jwt_claims
+        ::decode_token(&long_tokens.access_token, &long)
+        .unwrap();
+    assert_eq!(short_claims.exp - short_claims.iat, 60);
+    assert_eq!(long_claims.exp - long_claims.iat, 600);
+
+    // a token signed with one configuration is rejected by the other
+    let result = jwt_claims::decode_token::<AccessClaims>(&short_tokens.access_token, &long);
+    assert!(matches!(result, Err(AuthError::WrongCredentials)));
+}
@@ -0,0 +1,95 @@
+use axum_web::{
+    application::{ config, config_service, shutdown::Shutdown, state::{ AppState, SharedState } },
+    infrastructure::{ postgres, redis },
+};
+use std::{ sync::Arc, time::Duration };
+
+const CONFIG_TOML: &str = r#"
+[postgres]
+user = "admin"
+password = "pswd1234"
+db = "axum_web"
+
+[jwt]
+secret = "0123456789abcdef0123456789abcdef"
+expire_access_token_seconds = 60
+"#;
+
+#[test]
+fn config_reload_diff_test() {
+    let current = config::from_toml(CONFIG_TOML).unwrap();
+    let new = config
+        ::from_toml(
+            &CONFIG_TOML.replace("expire_access_token_seconds = 60", "expire_access_token_seconds = 120")
+                .replace("[postgres]", "[service]\nport = 4000\n\n[postgres]")
+        )
+        .unwrap();
+
+    let reload = current.reload(&new);
+    assert_eq!(reload.changed, vec!["jwt_expire_access_token_seconds: 60 -> 120"]);
+    assert_eq!(reload.ignored, vec!["service_port"]);
+    assert_eq!(reload.config.jwt_expire_access_token_seconds, 120);
+    assert_eq!(reload.config.service_port, current.service_port);
+}
+
+#[tokio::test]
+async fn config_reload_test() {
+    let config_file = std::env::temp_dir().join(format!("config_reload_{}.toml", std::process::id()));
+    let write_filter = |filter: &str| {
+        let contents = format!("[config]\nwatch_interval_seconds = 0\n\n[log]\nfilter = \"{filter}\"\n");
+        std::fs::write(&config_file, contents).unwrap();
+    };
+    write_filter("info");
+    std::env::set_var("ENV_TEST", "1");
+    std::env::set_var("CONFIG_FILE", &config_file);
+    std::env::remove_var("LOG_FILTER");
+
+    config::load();
+    let config = config::shared();
+    assert_eq!(config.log_filter, "info");
+    let redis = redis::open(&config).await;
+    let pgpool = postgres::pgpool(&config).await;
+    let state: SharedState = Arc::new(AppState::new(config, pgpool, redis, Shutdown::new(), Default::default()));
+
+    // a valid change is applied
+    write_filter("debug");
+    let changed = config_service::reload(&state).unwrap();
+    assert_eq!(changed, vec![r#"log_filter: "info" -> "debug""#]);
+    assert_eq!(state.config().log_filter, "debug");
+    // the global configuration the state was built from is swapped as well
+    assert_eq!(config::get().log_filter, "debug");
+
+    // an invalid configuration is rejected, the current one is kept
+    std::fs::write(&config_file, "[log\nfilter = ").unwrap();
+    assert!(config_service::reload(&state).is_err());
+    write_filter("axum_web=[");
+    let problems = config_service::reload(&state).unwrap_err().0;
+    assert!(problems[0].starts_with("LOG_FILTER"), "{problems:?}");
+    assert_eq!(state.config().log_filter, "debug");
+
+    // SIGHUP triggers a reload
+    tokio::spawn(config_service::watch(state.clone()));
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    write_filter("warn");
+    let status = std::process::Command
+        ::new("kill")
+        .args(["-HUP", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+    for _ in 0..50 {
+        if state.config().log_filter == "warn" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(state.config().log_filter, "warn");
+
+    // the keys of the file overridden by the environment are reported
+    assert!(config::shadowed_keys().is_empty());
+    std::env::set_var("LOG_FILTER", "error");
+    assert_eq!(config::shadowed_keys(), vec!["LOG_FILTER"]);
+    std::env::remove_var("LOG_FILTER");
+
+    std::fs::remove_file(&config_file).unwrap();
+}
@@ -0,0 +1,168 @@
+use axum_web::application::config::{ self, LogFormat };
+use serial_test::serial;
+
+const SECRET: &str = "0123456789abcdef0123456789abcdef";
+
+const CONFIG_TOML: &str = r#"
+[service]
+host = "0.0.0.0"
+port = 8080
+
+[postgres]
+user = "admin"
+password = "from-file"
+db = "axum_web"
+
+[jwt]
+secret = "0123456789abcdef0123456789abcdef"
+expire_access_token_seconds = 60
+
+[log]
+format = "json"
+"#;
+
+#[test]
+#[serial]
+fn config_file_and_defaults_test() {
+    let config = config::from_toml(CONFIG_TOML).unwrap();
+    assert_eq!(config.service_host, "0.0.0.0");
+    assert_eq!(config.service_port, 8080);
+    assert_eq!(config.postgres_password, "from-file");
+    assert_eq!(config.jwt_secret, SECRET);
+    assert_eq!(config.jwt_expire_access_token_seconds, 60);
+    assert_eq!(config.log_format, LogFormat::Json);
+
+    // defaults
+    assert_eq!(config.redis_port, 6379);
+    assert_eq!(config.postgres_connection_pool, 5);
+    assert!(config.jwt_enable_revoked_tokens);
+    assert_eq!(config.otel_exporter_otlp_endpoint, None);
+}
+
+#[test]
+#[serial]
+fn config_env_override_test() {
+    let secret_file = std::env::temp_dir().join(format!("postgres_password_{}", std::process::id()));
+    std::fs::write(&secret_file, "from-secret-file\n").unwrap();
+
+    std::env::set_var("SERVICE_PORT", "9090");
+    std::env::set_var("POSTGRES_PASSWORD_FILE", &secret_file);
+    let config = config::from_toml(CONFIG_TOML);
+    std::env::remove_var("SERVICE_PORT");
+    std::env::remove_var("POSTGRES_PASSWORD_FILE");
+    std::fs::remove_file(&secret_file).unwrap();
+
+    let config = config.unwrap();
+    assert_eq!(config.service_port, 9090);
+    assert_eq!(config.postgres_password, "from-secret-file");
+}
+
+#[test]
+#[serial]
+fn config_file_only_test() {
+    std::env::set_var("SERVICE_PORT", "9090");
+    let config = config::from_toml_only(CONFIG_TOML);
+    std::env::remove_var("SERVICE_PORT");
+
+    // the environment is ignored
+    assert_eq!(config.unwrap().service_port, 8080);
+}
+
+#[test]
+#[serial]
+fn config_validation_test() {
+    std::env::set_var("JWT_SECRET_FILE", "/nonexistent/jwt_secret");
+    let result = config::from_toml(
+        r#"
+        [service]
+        port = "http"
+        unix_socket_mode = "999"
+
+        [postgres]
+        user = "admin"
+        connection_pool = 0
+
+        [rate_limit]
+        rules = ["/v1/auth/login=10"]
+
+        [log]
+        format = "xml"
+        "#
+    );
+    std::env::remove_var("JWT_SECRET_FILE");
+
+    // all the problems are reported at once
+    let problems = result.unwrap_err().0;
+    for expected in [
+        "JWT_SECRET_FILE /nonexistent/jwt_secret",
+        "POSTGRES_PASSWORD is missing",
+        "POSTGRES_DB is missing",
+        "SERVICE_PORT has an invalid value: http",
+        "SERVICE_UNIX_SOCKET_MODE has an invalid value: 999",
+        "LOG_FORMAT has an invalid value: xml",
+        "RATE_LIMIT_RULES has an invalid value: /v1/auth/login=10",
+        "POSTGRES_CONNECTION_POOL must be positive",
+    ] {
+        assert!(
+            problems.iter().any(|problem| problem.starts_with(expected)),
+            "{expected} not in {problems:#?}"
+        );
+    }
+}
+
+#[test]
+#[serial]
+fn config_cors_validation_test() {
+    let result = config::from_toml(
+        &format!(
+            r#"{CONFIG_TOML}
+            [cors]
+            allowed_origins = ["*", "app.example.com", "https://*.example.com"]
+            allowed_methods = ["GET", "GE T"]
+            allow_credentials = true
+            "#
+        )
+    );
+
+    let problems = result.unwrap_err().0;
+    assert_eq!(
+        problems,
+        vec![
+            "CORS_ALLOWED_ORIGINS has an invalid origin: app.example.com",
+            "CORS_ALLOWED_METHODS has an invalid method: GE T",
+            "CORS_ALLOW_CREDENTIALS cannot be used with any origin (*)"
+        ]
+    );
+}
+
+#[test]
+fn config_debug_redaction_test() {
+    let config = config
+        ::from_toml_only(
+            r#"
+            [postgres]
+            user = "admin"
+            password = "postgresSECRETvalue"
+            db = "axum_web"
+
+            [jwt]
+            secret = "jwtSECRETvalue-0123456789abcdef0123456789"
+
+            [password]
+            pepper = "pepperSECRETvalue"
+
+            [captcha]
+            kind = "static"
+            static_token = "captchaSECRETvalue"
+            "#
+        )
+        .unwrap();
+
+    for debug in [format!("{:?}", config), format!("{:#?}", config)] {
+        assert!(!debug.contains("SECRET"), "{debug}");
+        assert_eq!(debug.matches("[REDACTED]").count(), 4, "{debug}");
+        assert!(debug.contains("postgres_user: \"admin\""), "{debug}");
+    }
+    assert!(format!("{:?}", config).contains("password_pepper: Some([REDACTED])"));
+    assert!(format!("{:?}", config).contains("captcha_static_token: Some([REDACTED])"));
+}
@@ -0,0 +1,77 @@
+use axum_web::{ api::cors::OriginPattern, application::config };
+use reqwest::{ header, Method, StatusCode };
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+async fn preflight(path: &str, origin: &str) -> reqwest::Response {
+    let url = format!("{}{}", config::get().service_http_addr(), path);
+    reqwest::Client
+        ::new()
+        .request(Method::OPTIONS, url)
+        .header(header::ORIGIN, origin)
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "authorization")
+        .send().await
+        .unwrap()
+}
+
+#[test]
+fn origin_pattern_test() {
+    let exact = OriginPattern::new("http://localhost:8080");
+    assert!(exact.matches("http://localhost:8080"));
+    assert!(!exact.matches("http://localhost:8081"));
+
+    let subdomain = OriginPattern::new("https://*.example.com");
+    assert!(subdomain.matches("https://app.example.com"));
+    assert!(subdomain.matches("https://a.b.example.com"));
+    assert!(!subdomain.matches("https://example.com"));
+    assert!(!subdomain.matches("http://app.example.com"));
+    assert!(!subdomain.matches("https://app.example.com.evil.com"));
+    assert!(!subdomain.matches("https://evil.com/.example.com"));
+}
+
+#[tokio::test]
+#[serial]
+async fn cors_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    // allowed subdomain, credentials are allowed
+    let response = preflight("/v1/users", "https://app.example.com").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let headers = response.headers();
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://app.example.com");
+    assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+    assert_eq!(headers[header::ACCESS_CONTROL_MAX_AGE], "600");
+    assert!(headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str().unwrap().contains("PATCH"));
+
+    // exact origin
+    let response = preflight("/v1/users", "http://localhost:8080").await;
+    assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "http://localhost:8080");
+
+    // not allowed origins
+    for origin in ["https://evil.com", "https://example.com", "http://localhost:3001"] {
+        let response = preflight("/v1/users", origin).await;
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none(), "{origin}");
+    }
+
+    // the public heartbeat accepts any origin without credentials
+    let url = format!("{}/v1/heartbeat/1", config::get().service_http_addr());
+    let response = reqwest::Client
+        ::new()
+        .get(url)
+        .header(header::ORIGIN, "https://evil.com")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+    assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    assert!(
+        response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS]
+            .to_str()
+            .unwrap()
+            .contains("x-request-id")
+    );
+}
@@ -0,0 +1,174 @@
+use axum_web::{
+    application::{ config::Config, security::jwt_auth },
+    domain::models::user::User,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::{ path::{ Path, PathBuf }, sync::Arc, time::Duration };
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &str = "Correct-Horse-Battery-42";
+const VERIFY_URL: &str = "https://example.com/verify?token=";
+
+async fn start_api(mail_dir: &Path) -> Arc<Config> {
+    utils::start_api_with(&[
+        ("MAILER_KIND", "file"),
+        ("MAILER_FILE_DIR", mail_dir.to_str().unwrap()),
+        ("EMAIL_VERIFICATION_REQUIRED", "true"),
+        ("EMAIL_VERIFICATION_URL", VERIFY_URL),
+        ("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", "60"),
+        ("JWT_EXPIRE_REFRESH_TOKEN_SECONDS", "120"),
+    ]).await
+}
+
+// the token of the latest of the expected messages to the email, they are written in the background
+async fn token(mail_dir: &Path, email: &str, expected: usize) -> String {
+    for _ in 0..50 {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(mail_dir) {
+            Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+            Err(_) => Vec::new(),
+        };
+        paths.sort();
+        let mails: Vec<String> = paths
+            .iter()
+            .map(|path| std::fs::read_to_string(path).unwrap())
+            .filter(|mail| mail.contains(&format!("To: {}\r\n", email)))
+            .collect();
+        if mails.len() >= expected {
+            assert_eq!(mails.len(), expected);
+            let link = mails[expected - 1].lines().find(|line| line.starts_with(VERIFY_URL)).unwrap();
+            return link.trim_start_matches(VERIFY_URL).to_string();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("expected {} mails to {}", expected, email);
+}
+
+#[tokio::test]
+async fn email_verification_test() {
+    let mail_dir = std::env::temp_dir().join(format!("mail-{}", Uuid::new_v4()));
+    let config = start_api(&mail_dir).await;
+    let client = reqwest::Client::new();
+    let base_url = config.service_http_addr();
+    let id = Uuid::new_v4();
+    let mut user = User {
+        id,
+        username: format!("verify-{}", id),
+        email: "not-an-email".to_string(),
+        password: PASSWORD.to_string(),
+        active: true,
+        roles: "guest".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    };
+    let admin = User { id: Uuid::new_v4(), roles: "admin".to_string(), ..user.clone() };
+    let admin_access_token = jwt_auth::generate_tokens(admin, &config).access_token;
+    let login = |username: String| {
+        client
+            .post(format!("{}/v1/auth/login", base_url))
+            .json(&json!({ "username": username, "password": PASSWORD }))
+            .send()
+    };
+    let verify = |token: String| {
+        client.post(format!("{}/v1/auth/email/verify", base_url)).json(&json!({ "token": token })).send()
+    };
+
+    // the emails are checked
+    let response = client
+        .post(format!("{}/v1/users", base_url))
+        .bearer_auth(&admin_access_token)
+        .json(&user)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["errors"][0]["field"], "email");
+
+    // a new user gets a token and cannot log in until the email is verified
+    user.email = format!("verify-{}@example.com", id);
+    let response = client
+        .post(format!("{}/v1/users", base_url))
+        .bearer_auth(&admin_access_token)
+        .json(&user)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: User = response.json().await.unwrap();
+    assert!(created.email_verified_at.is_none());
+    let token_1 = token(&mail_dir, &user.email, 1).await;
+
+    let response = login(user.username.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["type"], "/problems/auth.email_not_verified");
+
+    let response = verify("wrong".to_string()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = verify(token_1.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = verify(token_1).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = login(user.username.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    let access_token = json["access_token"].as_str().unwrap().to_string();
+
+    // a changed email is verified again
+    let me_url = format!("{}/v1/users/me", base_url);
+    let email_2 = format!("verify-2-{}@example.com", id);
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &email_2, "current_password": PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert!(json["email_verified_at"].is_null());
+    token(&mail_dir, &email_2, 1).await;
+    let response = login(user.username.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // a new token on request, it verifies only the email it was sent to
+    let response = client
+        .post(format!("{}/v1/auth/email/resend", base_url))
+        .json(&json!({ "email": &email_2 }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let token_2 = token(&mail_dir, &email_2, 2).await;
+
+    let email_3 = format!("verify-3-{}@example.com", id);
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &email_3, "current_password": PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let token_3 = token(&mail_dir, &email_3, 1).await;
+    let response = verify(token_2).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = verify(token_3).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client.get(&me_url).bearer_auth(&access_token).send().await.unwrap();
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["email"], email_3);
+    assert!(json["email_verified_at"].is_string());
+    let response = login(user.username.clone()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = client
+        .delete(format!("{}/v1/users/{}", base_url, id))
+        .bearer_auth(&admin_access_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    std::fs::remove_dir_all(&mail_dir).unwrap();
+}
@@ -0,0 +1,37 @@
+use axum_web::application::config;
+use reqwest::StatusCode;
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+#[tokio::test]
+#[serial]
+async fn health_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+    let service_http_addr = config::get().service_http_addr();
+
+    let url = format!("{}/health/live", service_http_addr);
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["status"], "alive");
+
+    let url = format!("{}/health/ready", service_http_addr);
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["ready"], true);
+    assert_eq!(json["postgres"]["status"], "up");
+    assert_eq!(json["redis"]["status"], "up");
+    assert!(json["postgres"]["latency_ms"].is_f64());
+    assert!(json["redis"]["latency_ms"].is_f64());
+    // the failure details are only logged
+    assert!(json["postgres"].get("error").is_none());
+    assert!(json["redis"].get("error").is_none());
+
+    let pool = &json["postgres"]["pool"];
+    assert_eq!(pool["max"], config::get().postgres_connection_pool);
+    assert!(pool["saturation"].as_f64().unwrap() <= 1.0);
+}
@@ -1,5 +1,4 @@
 use axum_web::application::app_const::*;
-use uuid::Uuid;
 use serial_test::serial;
 
 pub mod common;
@@ -14,8 +13,8 @@ async fn heartbeat_test() {
     // load the test configuration and start the api server
     utils::start_api().await;
 
-    let heartbeat_id = Uuid::new_v4().to_string();
-    let url = utils::build_url(API_V1, PATH_HEARTBEAT, &heartbeat_id);
+    let heartbeat_id = "1234";
+    let url = utils::build_url(API_V1, PATH_HEARTBEAT, heartbeat_id);
 
     // fetch using reqwest
     let response = reqwest::get(url.as_str()).await.unwrap();
@@ -0,0 +1,93 @@
+use serial_test::serial;
+use std::os::unix::{ fs::PermissionsExt, io::IntoRawFd };
+use std::path::Path;
+use tokio::{ io::{ AsyncReadExt, AsyncWriteExt }, net::UnixStream };
+
+pub mod common;
+use common::utils;
+
+async fn get_over_unix_socket(path: &Path, uri: &str) -> String {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", uri);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+#[serial]
+async fn unix_socket_test() {
+    let path = std::env::temp_dir().join(format!("axum_web_{}.sock", std::process::id()));
+
+    // a socket file left by a previous run is replaced
+    drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+    assert!(path.exists());
+
+    utils::start_api_with(&[
+        ("SERVICE_UNIX_SOCKET", path.to_str().unwrap()),
+        ("SERVICE_UNIX_SOCKET_MODE", "600"),
+        ("RATE_LIMIT_ENABLED", "true"),
+        ("RATE_LIMIT_RULES", "/=1/60:ip"),
+    ]).await;
+
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+
+    let response = get_over_unix_socket(&path, "/health/live").await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+    // the clients without an ip do not share a rate limit
+    for _ in 0..2 {
+        let response = get_over_unix_socket(&path, "/v1/heartbeat/1").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.lines().any(|line| line.starts_with("ratelimit-limit:")), "{response}");
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn systemd_socket_activation_test() {
+    // the socket systemd would pass to the service
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let fd = listener.into_raw_fd().to_string();
+    let pid = std::process::id().to_string();
+
+    // the variables are read when the listener is bound
+    std::env::set_var("LISTEN_FDS", "1");
+    std::env::set_var("LISTEN_FDS_FIRST_FD", fd);
+    std::env::set_var("LISTEN_PID", pid);
+    utils::start_api_with(&[]).await;
+    std::env::remove_var("LISTEN_FDS_FIRST_FD");
+    assert!(std::env::var("LISTEN_FDS").is_err());
+
+    let response = reqwest::get(format!("http://{}/health/live", addr)).await.unwrap();
+    assert_eq!(response.status(), reqwest::StatusCode::OK);
+}
+
+#[tokio::test]
+#[serial]
+async fn systemd_unix_socket_tls_test() {
+    let dir = std::env::temp_dir().join(format!("axum_web_tls_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let certified_key = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+    let (cert_path, key_path) = (dir.join("server.pem"), dir.join("server.key"));
+    std::fs::write(&cert_path, certified_key.cert.pem()).unwrap();
+    std::fs::write(&key_path, certified_key.key_pair.serialize_pem()).unwrap();
+
+    // a Unix socket passed by systemd is not served in plain text when TLS is configured
+    let path = dir.join("axum_web.sock");
+    let fd = std::os::unix::net::UnixListener::bind(&path).unwrap().into_raw_fd().to_string();
+    std::env::set_var("LISTEN_FDS", "1");
+    std::env::set_var("LISTEN_FDS_FIRST_FD", fd);
+    std::env::set_var("LISTEN_PID", std::process::id().to_string());
+    let result = utils::try_start_api_with(&[
+        ("TLS_CERT_PATH", cert_path.to_str().unwrap()),
+        ("TLS_KEY_PATH", key_path.to_str().unwrap()),
+    ]).await;
+    std::env::remove_var("LISTEN_FDS_FIRST_FD");
+    assert!(result.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
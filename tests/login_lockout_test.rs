@@ -0,0 +1,182 @@
+use axum_web::{
+    application::{ config::Config, security::{ jwt_auth, password } },
+    domain::models::user::User,
+};
+use reqwest::{ header, StatusCode };
+use serde_json::json;
+use serial_test::serial;
+use std::{ net::Ipv4Addr, sync::Arc, time::Duration };
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &str = "correct horse battery staple";
+
+async fn start_api() -> Arc<Config> {
+    utils::start_api_with(&[
+        ("SERVICE_TRUST_FORWARDED_FOR", "true"),
+        ("LOGIN_LOCKOUT_ENABLED", "true"),
+        ("LOGIN_BACKOFF_BASE_SECONDS", "1"),
+        ("LOGIN_BACKOFF_MAX_SECONDS", "1"),
+        ("LOGIN_LOCKOUT_THRESHOLD", "3"),
+        ("LOGIN_LOCKOUT_IP_THRESHOLD", "5"),
+        ("LOGIN_LOCKOUT_SECONDS", "60"),
+    ]).await
+}
+
+// a new client ip on each run, the counters are kept in redis
+fn random_ip() -> String {
+    let bytes = Uuid::new_v4().into_bytes();
+    Ipv4Addr::new(10, bytes[0], bytes[1], bytes[2]).to_string()
+}
+
+async fn login(config: &Config, username: &str, password: &str, ip: &str) -> reqwest::Response {
+    reqwest::Client::new()
+        .post(format!("{}/v1/auth/login", config.service_http_addr()))
+        .header("x-forwarded-for", ip)
+        .json(&json!({ "username": username, "password": password }))
+        .send().await
+        .unwrap()
+}
+
+async fn unlock(config: &Config, access_token: &str, body: serde_json::Value) -> StatusCode {
+    reqwest::Client::new()
+        .post(format!("{}/v1/auth/unlock", config.service_http_addr()))
+        .bearer_auth(access_token)
+        .json(&body)
+        .send().await
+        .unwrap()
+        .status()
+}
+
+fn user(roles: &str, config: &Config) -> User {
+    let id = Uuid::new_v4();
+    User {
+        id,
+        username: format!("lockout-{}", id),
+        email: format!("lockout-{}@example.com", id),
+        password: password::hash_password(PASSWORD.as_bytes(), config),
+        active: true,
+        roles: roles.to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    }
+}
+
+fn admin_access_token(config: &Config) -> String {
+    jwt_auth::generate_tokens(user("admin", config), config).access_token
+}
+
+async fn insert_user(config: &Config, user: &User) -> sqlx::PgPool {
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    sqlx::query("INSERT INTO users (id, username, email, password, active, roles, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, now(), now())")
+        .bind(user.id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password)
+        .bind(user.active)
+        .bind(&user.roles)
+        .execute(&pgpool).await
+        .unwrap();
+    pgpool
+}
+
+fn retry_after(response: &reqwest::Response) -> u64 {
+    response.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap()
+}
+
+// the failed logins of the username from a new ip until the username is locked,
+// the statuses and the retry delays
+async fn fail_until_locked(config: &Config, username: &str) -> Vec<(StatusCode, Option<u64>)> {
+    let ip = &random_ip();
+    let mut responses = Vec::new();
+    let mut record = |response: &reqwest::Response| {
+        let retry_after = response.headers().get(header::RETRY_AFTER).map(|_| retry_after(response));
+        responses.push((response.status(), retry_after.map(|seconds| seconds.min(2))));
+    };
+
+    record(&login(config, username, "wrong", ip).await);
+    // blocked during the backoff, whatever the password
+    record(&login(config, username, "wrong", ip).await);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    record(&login(config, username, "wrong", ip).await);
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    record(&login(config, username, "wrong", ip).await);
+    // locked after the third failure
+    let response = login(config, username, PASSWORD, ip).await;
+    assert!(retry_after(&response) > 50);
+    record(&response);
+    responses
+}
+
+#[tokio::test]
+#[serial]
+async fn login_lockout_test() {
+    let config = start_api().await;
+    let ip = random_ip();
+    let user = user("guest", &config);
+    let pgpool = insert_user(&config, &user).await;
+
+    let unknown_user = format!("nobody-{}", Uuid::new_v4());
+    let unknown = fail_until_locked(&config, &unknown_user).await;
+    let existing = fail_until_locked(&config, &user.username).await;
+
+    let expected = vec![
+        (StatusCode::UNAUTHORIZED, None),
+        (StatusCode::TOO_MANY_REQUESTS, Some(1)),
+        (StatusCode::UNAUTHORIZED, None),
+        (StatusCode::UNAUTHORIZED, None),
+        (StatusCode::TOO_MANY_REQUESTS, Some(2)),
+    ];
+    assert_eq!(unknown, expected);
+    // the existing usernames cannot be told apart
+    assert_eq!(existing, expected);
+
+    let response = login(&config, &unknown_user, "wrong", &ip).await;
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/problem+json");
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["status"], 429);
+    assert_eq!(json["type"], "/problems/auth.too_many_attempts");
+
+    // only an admin unlocks and a username or an ip is required
+    let access_token = admin_access_token(&config);
+    let user_access_token = jwt_auth::generate_tokens(user.clone(), &config).access_token;
+    assert_eq!(
+        unlock(&config, &user_access_token, json!({ "username": &user.username })).await,
+        StatusCode::UNAUTHORIZED
+    );
+    assert_eq!(unlock(&config, &access_token, json!({})).await, StatusCode::BAD_REQUEST);
+
+    assert_eq!(unlock(&config, &access_token, json!({ "username": &user.username })).await, StatusCode::OK);
+    let response = login(&config, &user.username, PASSWORD, &ip).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&pgpool).await.unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn login_ip_lockout_test() {
+    let config = start_api().await;
+    let ip = random_ip();
+
+    // the failures of the different usernames from the ip are counted together
+    for _ in 0..5 {
+        let response = login(&config, &format!("nobody-{}", Uuid::new_v4()), "wrong", &ip).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    let response = login(&config, &format!("nobody-{}", Uuid::new_v4()), "wrong", &ip).await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(retry_after(&response) > 50);
+
+    // the other clients are not locked
+    let response = login(&config, &format!("nobody-{}", Uuid::new_v4()), "wrong", &random_ip()).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let access_token = admin_access_token(&config);
+    assert_eq!(unlock(&config, &access_token, json!({ "ip": ip })).await, StatusCode::OK);
+    let response = login(&config, &format!("nobody-{}", Uuid::new_v4()), "wrong", &ip).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
@@ -0,0 +1,73 @@
+use axum_web::application::{ config::Config, security::password };
+use reqwest::StatusCode;
+use serde_json::json;
+use std::time::{ Duration, Instant };
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const SAMPLES: usize = 5;
+
+async fn insert_user(pgpool: &sqlx::PgPool, config: &Config, username: &str, active: bool) -> Uuid {
+    let id = Uuid::new_v4();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password, active, roles, created_at, updated_at) \
+         VALUES ($1, $2, $3, $4, $5, 'guest', now(), now())"
+    )
+        .bind(id)
+        .bind(username)
+        .bind(format!("{}@example.com", username))
+        .bind(password::hash_password(b"correct password", config))
+        .bind(active)
+        .execute(pgpool).await
+        .unwrap();
+    id
+}
+
+// the median duration of the failed logins of the username
+async fn failed_login_median(client: &reqwest::Client, config: &Config, username: &str) -> Duration {
+    let mut durations = Vec::with_capacity(SAMPLES);
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        let response = client
+            .post(format!("{}/v1/auth/login", config.service_http_addr()))
+            .json(&json!({ "username": username, "password": "wrong password" }))
+            .send().await
+            .unwrap();
+        durations.push(start.elapsed());
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+    durations.sort();
+    durations[SAMPLES / 2]
+}
+
+#[tokio::test]
+async fn login_timing_test() {
+    let config = utils::start_api_with(&[]).await;
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    let active_username = format!("timing-{}", Uuid::new_v4());
+    let inactive_username = format!("timing-{}", Uuid::new_v4());
+    let active_id = insert_user(&pgpool, &config, &active_username, true).await;
+    let inactive_id = insert_user(&pgpool, &config, &inactive_username, false).await;
+
+    let client = reqwest::Client::new();
+    let existing = failed_login_median(&client, &config, &active_username).await;
+    let inactive = failed_login_median(&client, &config, &inactive_username).await;
+    let unknown = failed_login_median(&client, &config, &format!("timing-{}", Uuid::new_v4())).await;
+
+    // without a password verification the unknown and the inactive users fail in a fraction of the time
+    for (name, median) in [("inactive", inactive), ("unknown", unknown)] {
+        let ratio = median.as_secs_f64() / existing.as_secs_f64();
+        assert!(
+            (0.5..2.0).contains(&ratio),
+            "{name} user: {median:?}, existing user: {existing:?}"
+        );
+    }
+
+    sqlx::query("DELETE FROM users WHERE id = $1 OR id = $2")
+        .bind(active_id)
+        .bind(inactive_id)
+        .execute(&pgpool).await
+        .unwrap();
+}
@@ -0,0 +1,33 @@
+use axum_web::application::config;
+use reqwest::StatusCode;
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+const API_V1: &str = "v1";
+
+#[tokio::test]
+#[serial]
+async fn metrics_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    let url = utils::build_url(API_V1, "heartbeat", "1");
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let url = format!("{}/metrics", config::get().service_http_addr());
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let metrics = response.text().await.unwrap();
+
+    // requests are labeled by the matched route instead of the actual path
+    assert!(
+        metrics.contains(
+            r#"http_requests_total{method="GET",route="/v1/heartbeat/:id",status="200"}"#
+        )
+    );
+    assert!(metrics.contains("http_requests_duration_seconds_bucket{"));
+    assert!(metrics.contains(r#"postgres_pool_connections{state="max"}"#));
+}
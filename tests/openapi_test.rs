@@ -0,0 +1,51 @@
+use axum_web::application::config;
+use reqwest::StatusCode;
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+#[tokio::test]
+#[serial]
+async fn openapi_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    let url = format!("{}/openapi.json", config::get().service_http_addr());
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["openapi"], "3.1.0");
+
+    let paths = json["paths"].as_object().unwrap();
+    assert!(paths.contains_key("/v1/heartbeat/{id}"));
+    assert!(paths.contains_key("/v1/auth/login"));
+    assert!(paths.contains_key("/v1/users/{id}"));
+    assert_eq!(paths["/v1/users/{id}"]["get"]["security"][0]["bearer_auth"], serde_json::json!([]));
+
+    let components = &json["components"];
+    assert_eq!(components["securitySchemes"]["bearer_auth"]["scheme"], "bearer");
+    assert!(components["schemas"]["ProblemDetails"].is_object());
+    assert!(components["schemas"]["User"].is_object());
+}
+
+#[cfg(feature = "swagger-ui")]
+#[tokio::test]
+#[serial]
+async fn swagger_ui_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    // the page and its assets are served by the api, the page loads the specification of the api
+    let url = format!("{}/swagger-ui/", config::get().service_http_addr());
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("swagger-ui-bundle.js"));
+
+    let response = reqwest::get(format!("{}swagger-ui-bundle.js", url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = reqwest::get(format!("{}swagger-initializer.js", url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.text().await.unwrap().contains("/openapi.json"));
+}
@@ -0,0 +1,141 @@
+use axum_web::{
+    application::{
+        config,
+        security::{ breached_passwords::BreachedPasswords, jwt_auth, password_policy },
+    },
+    domain::models::user::User,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use sha1::{ Digest, Sha1 };
+use std::io::Cursor;
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const BREACHED_PASSWORD: &str = "Summer-2024-Sunshine";
+const STRONG_PASSWORD: &str = "Correct-Horse-Battery-42";
+
+fn sha1_hex(password: &str) -> String {
+    format!("{:X}", Sha1::digest(password.as_bytes()))
+}
+
+fn corpus() -> String {
+    format!(
+        "{}:37\n{}:5\nnot a hash\n",
+        sha1_hex(BREACHED_PASSWORD),
+        sha1_hex("other-password").to_lowercase()
+    )
+}
+
+// the default policy: 12 to 128 characters of at least 3 character classes
+fn codes(password: &str, username: &str, email: &str, breached: &BreachedPasswords) -> Vec<String> {
+    let config = config
+        ::from_toml_only(
+            r#"
+            [postgres]
+            user = "admin"
+            password = "pswd1234"
+            db = "axum_web"
+
+            [jwt]
+            secret = "0123456789abcdef0123456789abcdef"
+            "#
+        )
+        .unwrap();
+    password_policy
+        ::validate("password", password, username, email, &config, breached)
+        .into_iter()
+        .map(|error| {
+            assert_eq!(error.field, "password");
+            error.code
+        })
+        .collect()
+}
+
+#[test]
+fn password_policy_test() {
+    let breached = BreachedPasswords::read(Cursor::new(corpus())).unwrap();
+    assert_eq!(breached.len(), 2);
+    assert!(breached.contains(BREACHED_PASSWORD));
+    assert!(breached.contains("other-password"));
+    assert!(!breached.contains(STRONG_PASSWORD));
+
+    let none = BreachedPasswords::default();
+    assert!(codes(STRONG_PASSWORD, "alice", "alice@example.com", &none).is_empty());
+    assert_eq!(codes("123", "alice", "alice@example.com", &none), ["too_short", "character_classes"]);
+    assert_eq!(codes(&"Aa1-".repeat(33), "alice", "alice@example.com", &none), ["too_long"]);
+    assert_eq!(codes("alllowercaseletters", "alice", "alice@example.com", &none), ["character_classes"]);
+    assert_eq!(codes("Alice-Example-1", "alice-example-1", "alice@example.com", &none), ["same_as_username"]);
+    assert_eq!(codes("Alice@Example.com", "alice", "alice@example.com", &none), ["same_as_email"]);
+    assert_eq!(codes(BREACHED_PASSWORD, "alice", "alice@example.com", &breached), ["breached"]);
+}
+
+#[tokio::test]
+async fn password_policy_api_test() {
+    let corpus_path = std::env::temp_dir().join(format!("breached-{}.txt", Uuid::new_v4()));
+    std::fs::write(&corpus_path, corpus()).unwrap();
+    let config = utils::start_api_with(&[("PASSWORD_BREACHED_CORPUS_PATH", corpus_path.to_str().unwrap())]).await;
+    std::fs::remove_file(&corpus_path).unwrap();
+
+    let id = Uuid::new_v4();
+    let mut user = User {
+        id,
+        username: format!("policy-{}", id),
+        email: format!("policy-{}@example.com", id),
+        password: BREACHED_PASSWORD.to_string(),
+        active: true,
+        roles: "guest".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    };
+    let client = reqwest::Client::new();
+    let users_url = format!("{}/v1/users", config.service_http_addr());
+    let admin = User { id: Uuid::new_v4(), roles: "admin".to_string(), ..user.clone() };
+    let admin_access_token = || jwt_auth::generate_tokens(admin.clone(), &config).access_token;
+
+    // the field errors are reported in the problem details
+    let response = client.post(&users_url).bearer_auth(admin_access_token()).json(&user).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["code"], "validation.failed");
+    assert_eq!(
+        json["errors"],
+        json!([{ "field": "password", "code": "breached", "message": "has appeared in a data breach, choose another one" }])
+    );
+
+    user.password = STRONG_PASSWORD.to_string();
+    let response = client.post(&users_url).bearer_auth(admin_access_token()).json(&user).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: User = response.json().await.unwrap();
+    assert_ne!(created.password, STRONG_PASSWORD);
+
+    // the stored hash sent back is kept, a new password is checked
+    let user_url = format!("{}/{}", users_url, id);
+    let response = client.put(&user_url).bearer_auth(admin_access_token()).json(&created).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: User = response.json().await.unwrap();
+    assert_eq!(updated.password, created.password);
+
+    let mut weak = created.clone();
+    weak.password = "123".to_string();
+    let response = client.put(&user_url).bearer_auth(admin_access_token()).json(&weak).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    let codes: Vec<&str> = json["errors"].as_array().unwrap().iter().map(|e| e["code"].as_str().unwrap()).collect();
+    assert_eq!(codes, ["too_short", "character_classes"]);
+
+    let response = client.delete(&user_url).bearer_auth(admin_access_token()).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn password_policy_missing_corpus_test() {
+    let corpus_path = std::env::temp_dir().join(format!("breached-{}.txt", Uuid::new_v4()));
+
+    // the server is not started, the process is not exited
+    let result = utils::try_start_api_with(&[("PASSWORD_BREACHED_CORPUS_PATH", corpus_path.to_str().unwrap())]).await;
+    assert!(result.is_err());
+}
@@ -0,0 +1,174 @@
+use axum_web::{
+    application::{ app::Plugins, config::Config, security::{ jwt_auth, password } },
+    domain::models::user::User,
+    infrastructure::mailer::{ Mail, Mailer, SendFuture },
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::{ path::{ Path, PathBuf }, sync::{ Arc, Mutex }, time::Duration };
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &str = "Correct-Horse-Battery-42";
+const NEW_PASSWORD: &str = "Purple-Monkey-Dishwasher-7";
+
+async fn start_api(mail_dir: &Path) -> Arc<Config> {
+    utils::start_api_with(&[
+        ("MAILER_KIND", "file"),
+        ("MAILER_FILE_DIR", mail_dir.to_str().unwrap()),
+        ("PASSWORD_RESET_URL", "https://example.com/reset?token="),
+        ("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", "60"),
+        ("JWT_EXPIRE_REFRESH_TOKEN_SECONDS", "120"),
+    ]).await
+}
+
+// the messages sent so far, they are written in the background
+async fn mails(mail_dir: &Path, expected: usize) -> Vec<String> {
+    for _ in 0..50 {
+        let mut paths: Vec<PathBuf> = match std::fs::read_dir(mail_dir) {
+            Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+            Err(_) => Vec::new(),
+        };
+        if paths.len() >= expected {
+            paths.sort();
+            return paths.iter().map(|path| std::fs::read_to_string(path).unwrap()).collect();
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("expected {} mails", expected);
+}
+
+fn token(mail: &str) -> String {
+    let link = mail.lines().find(|line| line.starts_with("https://example.com/reset?token=")).unwrap();
+    link.rsplit('=').next().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn password_reset_test() {
+    let mail_dir = std::env::temp_dir().join(format!("mail-{}", Uuid::new_v4()));
+    let config = start_api(&mail_dir).await;
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    let id = Uuid::new_v4();
+    let user = User {
+        id,
+        username: format!("reset-{}", id),
+        email: format!("reset-{}@example.com", id),
+        password: password::hash_password(PASSWORD.as_bytes(), &config),
+        active: true,
+        roles: "guest".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    };
+    sqlx::query("INSERT INTO users (id, username, email, password, active, roles, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, now(), now())")
+        .bind(user.id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password)
+        .bind(user.active)
+        .bind(&user.roles)
+        .execute(&pgpool).await
+        .unwrap();
+
+    let client = reqwest::Client::new();
+    let forgot_url = format!("{}/v1/auth/password/forgot", config.service_http_addr());
+    let reset_url = format!("{}/v1/auth/password/reset", config.service_http_addr());
+    let reset = |token: String, new_password: &str| {
+        client.post(&reset_url).json(&json!({ "token": token, "new_password": new_password })).send()
+    };
+
+    // the unknown emails are answered the same way, without a message
+    let response = client
+        .post(&forgot_url)
+        .json(&json!({ "email": format!("nobody-{}@example.com", id) }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let response = client.post(&forgot_url).json(&json!({ "email": &user.email })).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let mails = mails(&mail_dir, 1).await;
+    assert_eq!(mails.len(), 1);
+    assert!(mails[0].contains(&format!("To: {}\r\n", user.email)));
+    let token = token(&mails[0]);
+    // only the hash of the token is stored
+    let stored: i64 = sqlx::query_scalar("SELECT count(*) FROM password_reset_tokens WHERE token_hash = $1")
+        .bind(&token)
+        .fetch_one(&pgpool).await
+        .unwrap();
+    assert_eq!(stored, 0);
+
+    let response = reset("wrong".to_string(), NEW_PASSWORD).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["errors"][0]["field"], "token");
+    assert_eq!(json["errors"][0]["code"], "invalid");
+
+    // a rejected password keeps the token valid
+    let response = reset(token.clone(), "short").await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["errors"][0]["field"], "new_password");
+
+    let session = jwt_auth::generate_tokens(user.clone(), &config);
+    let response = reset(token.clone(), NEW_PASSWORD).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // the token is single-use and the existing sessions are revoked
+    let response = reset(token, NEW_PASSWORD).await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = client
+        .post(format!("{}/v1/auth/refresh", config.service_http_addr()))
+        .bearer_auth(&session.refresh_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client
+        .post(format!("{}/v1/auth/login", config.service_http_addr()))
+        .json(&json!({ "username": &user.username, "password": NEW_PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(user.id).execute(&pgpool).await.unwrap();
+    std::fs::remove_dir_all(&mail_dir).unwrap();
+}
+
+// keeps the messages in memory
+#[derive(Default)]
+struct MemoryMailer(Mutex<Vec<Mail>>);
+
+impl Mailer for MemoryMailer {
+    fn send<'a>(&'a self, mail: &'a Mail) -> SendFuture<'a> {
+        Box::pin(async move {
+            self.0.lock().unwrap().push(mail.clone());
+            Ok(())
+        })
+    }
+}
+
+#[tokio::test]
+async fn plugged_mailer_test() {
+    let mailer = Arc::new(MemoryMailer::default());
+    let plugins = Plugins { mailer: Some(mailer.clone()), ..Default::default() };
+    let config = utils::try_start_api_with_plugins(&[], plugins).await.unwrap();
+
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/auth/password/forgot", config.service_http_addr()))
+        .json(&json!({ "email": "admin@admin.com" }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    for _ in 0..50 {
+        if !mailer.0.lock().unwrap().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    let mails = mailer.0.lock().unwrap();
+    assert_eq!(mails.len(), 1);
+    assert_eq!(mails[0].to, "admin@admin.com");
+    assert_eq!(mails[0].subject, "Reset your password");
+}
@@ -0,0 +1,140 @@
+use axum_web::application::{ config::{ self, Config }, security::password };
+use reqwest::StatusCode;
+use serde_json::json;
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &[u8] = b"correct horse battery staple";
+
+fn config_with(password_settings: &str) -> Config {
+    config
+        ::from_toml_only(
+            &format!(
+                r#"
+                [postgres]
+                user = "admin"
+                password = "pswd1234"
+                db = "axum_web"
+
+                [jwt]
+                secret = "0123456789abcdef0123456789abcdef"
+
+                [password]
+                {password_settings}
+                "#
+            )
+        )
+        .unwrap()
+}
+
+#[test]
+fn password_hash_params_test() {
+    let weak = config_with("hash_memory_kib = 8192\nhash_iterations = 1");
+    let strong = config_with("hash_memory_kib = 8192\nhash_iterations = 2");
+
+    let hash = password::hash_password(PASSWORD, &weak);
+    assert!(hash.starts_with("$argon2id$v=19$m=8192,t=1,p=1$"), "{hash}");
+
+    // verified with the parameters of the hash, whatever the configured ones
+    assert!(password::verify_password(&hash, PASSWORD, &strong).is_ok());
+    assert!(password::verify_password(&hash, b"wrong", &strong).is_err());
+
+    assert!(password::needs_rehash(&hash, &strong));
+    assert!(!password::needs_rehash(&hash, &weak));
+    assert!(!password::needs_rehash(&password::hash_password(PASSWORD, &strong), &weak));
+}
+
+#[test]
+fn password_pepper_test() {
+    let peppered = config_with("hash_memory_kib = 8192\npepper = \"pepper-0123456789\"");
+    let other_pepper = config_with("hash_memory_kib = 8192\npepper = \"pepper-9876543210\"");
+    let no_pepper = config_with("hash_memory_kib = 8192");
+
+    let hash = password::hash_password(PASSWORD, &peppered);
+    assert!(password::verify_password(&hash, PASSWORD, &peppered).is_ok());
+    // the hashes are useless without the pepper
+    assert!(password::verify_password(&hash, PASSWORD, &other_pepper).is_err());
+    assert!(password::verify_password(&hash, PASSWORD, &no_pepper).is_err());
+    assert!(!password::needs_rehash(&hash, &peppered));
+
+    // the hashes made before the pepper was set are verified without it and replaced
+    let unpeppered_hash = password::hash_password(PASSWORD, &no_pepper);
+    assert!(password::verify_password(&unpeppered_hash, PASSWORD, &peppered).is_ok());
+    assert!(password::verify_password(&unpeppered_hash, b"wrong password", &peppered).is_err());
+    assert!(password::needs_rehash(&unpeppered_hash, &peppered));
+    assert!(!password::needs_rehash(&unpeppered_hash, &no_pepper));
+}
+
+#[test]
+fn password_config_test() {
+    let problems = config::from_toml_only(
+        r#"
+        [postgres]
+        user = "admin"
+        password = "pswd1234"
+        db = "axum_web"
+
+        [jwt]
+        secret = "0123456789abcdef0123456789abcdef"
+
+        [password]
+        hash_memory_kib = 1
+        pepper = ""
+        "#
+    ).unwrap_err().0;
+    assert!(
+        problems.iter().any(|p| p.starts_with("PASSWORD_HASH_MEMORY_KIB")),
+        "{problems:?}"
+    );
+    assert!(problems.contains(&"PASSWORD_PEPPER must not be empty".to_string()), "{problems:?}");
+}
+
+#[tokio::test]
+async fn password_rehash_on_login_test() {
+    let config = utils::start_api_with(&[]).await;
+
+    // a user hashed with weaker parameters than the configured ones
+    let mut weak = (*config).clone();
+    weak.password_hash_memory_kib = 8192;
+    weak.password_hash_iterations = 1;
+    let weak_hash = password::hash_password(PASSWORD, &weak);
+    let id = Uuid::new_v4();
+    let username = format!("rehash-{}", id);
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password, active, roles, created_at, updated_at) \
+         VALUES ($1, $2, $3, $4, true, 'guest', now(), now())"
+    )
+        .bind(id)
+        .bind(&username)
+        .bind(format!("{}@example.com", username))
+        .bind(&weak_hash)
+        .execute(&pgpool).await
+        .unwrap();
+
+    let login = || {
+        reqwest::Client::new()
+            .post(format!("{}/v1/auth/login", config.service_http_addr()))
+            .json(&json!({ "username": username, "password": std::str::from_utf8(PASSWORD).unwrap() }))
+            .send()
+    };
+    let stored_hash = || async {
+        sqlx::query_scalar::<_, String>("SELECT password FROM users WHERE id = $1")
+            .bind(id)
+            .fetch_one(&pgpool).await
+            .unwrap()
+    };
+
+    assert_eq!(login().await.unwrap().status(), StatusCode::OK);
+    let new_hash = stored_hash().await;
+    assert_ne!(new_hash, weak_hash);
+    assert!(!password::needs_rehash(&new_hash, &config), "{new_hash}");
+
+    // the upgraded hash is kept on the next login
+    assert_eq!(login().await.unwrap().status(), StatusCode::OK);
+    assert_eq!(stored_hash().await, new_hash);
+
+    sqlx::query("DELETE FROM users WHERE id = $1").bind(id).execute(&pgpool).await.unwrap();
+}
@@ -0,0 +1,29 @@
+use reqwest::StatusCode;
+use serial_test::serial;
+
+pub mod common;
+use common::utils;
+
+const API_V1: &str = "v1";
+
+#[tokio::test]
+#[serial]
+async fn route_not_found_problem_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    let url = utils::build_url(API_V1, "unknown", "route");
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let content_type = response.headers()[reqwest::header::CONTENT_TYPE].to_str().unwrap();
+    assert_eq!(content_type, "application/problem+json");
+
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["type"], "/problems/api.not_found");
+    assert_eq!(json["title"], "Not found");
+    assert_eq!(json["status"], 404);
+    assert_eq!(json["detail"], "Route not found");
+    assert_eq!(json["instance"], url.path());
+    assert_eq!(json["code"], "api.not_found");
+}
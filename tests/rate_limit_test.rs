@@ -0,0 +1,97 @@
+use axum_web::application::config::Config;
+use reqwest::{ header, StatusCode };
+use serial_test::serial;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+async fn start_api(vars: &[(&str, &str)]) -> Arc<Config> {
+    let vars: Vec<_> = [("RATE_LIMIT_ENABLED", "true")].into_iter().chain(vars.iter().copied()).collect();
+    utils::start_api_with(&vars).await
+}
+
+fn header_value(response: &reqwest::Response, name: &str) -> Option<u64> {
+    response.headers().get(name)?.to_str().ok()?.parse().ok()
+}
+
+#[tokio::test]
+#[serial]
+async fn rate_limit_redis_test() {
+    let config = start_api(&[
+        ("RATE_LIMIT_RULES", "/v1/heartbeat=3/60:api_key"),
+    ]).await;
+    let url = format!("{}/v1/heartbeat/1", config.service_http_addr());
+    let client = reqwest::Client::new();
+    // a new client on each run, the counters are kept in redis
+    let api_key = Uuid::new_v4().to_string();
+
+    for remaining in [2, 1, 0] {
+        let response = client.get(&url).header("x-api-key", &api_key).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(header_value(&response, "ratelimit-limit"), Some(3));
+        assert_eq!(header_value(&response, "ratelimit-remaining"), Some(remaining));
+        assert!(header_value(&response, "ratelimit-reset").unwrap() <= 60);
+    }
+
+    let response = client.get(&url).header("x-api-key", &api_key).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "application/problem+json");
+    assert_eq!(header_value(&response, "ratelimit-remaining"), Some(0));
+    let retry_after = header_value(&response, "retry-after").unwrap();
+    assert!((1..=120).contains(&retry_after), "{retry_after}");
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["status"], 429);
+
+    // the other clients are counted separately
+    let response = client
+        .get(&url)
+        .header("x-api-key", Uuid::new_v4().to_string())
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // the routes without a rule are not limited
+    let response = client.get(format!("{}/health/live", config.service_http_addr())).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get("ratelimit-limit").is_none());
+}
+
+#[tokio::test]
+#[serial]
+async fn rate_limit_login_test() {
+    let config = start_api(&[
+        ("RATE_LIMIT_STORE", "memory"),
+        ("RATE_LIMIT_RULES", "/=100/60:ip,/v1/auth/login=2/60:ip"),
+    ]).await;
+    let url = format!("{}/v1/auth/login", config.service_http_addr());
+    let client = reqwest::Client::new();
+    let login = || {
+        client
+            .post(&url)
+            .json(&serde_json::json!({ "username": "nobody", "password": "wrong" }))
+            .send()
+    };
+
+    for _ in 0..2 {
+        let response = login().await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(header_value(&response, "ratelimit-limit"), Some(2));
+    }
+    let response = login().await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(header_value(&response, "retry-after").unwrap() >= 1);
+
+    // the other routes follow the rule of the shorter path
+    let response = client.get(format!("{}/v1/heartbeat/1", config.service_http_addr())).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(header_value(&response, "ratelimit-limit"), Some(100));
+
+    // the probes and the metrics are never limited
+    for path in ["/health/live", "/health/ready", "/metrics"] {
+        let response = client.get(format!("{}{}", config.service_http_addr(), path)).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", path);
+        assert!(response.headers().get("ratelimit-limit").is_none(), "{}", path);
+    }
+}
@@ -0,0 +1,311 @@
+use axum_web::{
+    application::{ app::Plugins, config::Config, repository::user_repo, security::jwt_auth },
+    domain::models::user::User,
+    infrastructure::captcha::{ Captcha, CaptchaError, VerifyFuture },
+};
+use reqwest::StatusCode;
+use serde_json::{ json, Value };
+use serial_test::serial;
+use std::{ net::IpAddr, sync::Arc };
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &str = "Correct-Horse-Battery-42";
+
+async fn register(config: &Config, body: Value) -> (StatusCode, Value) {
+    let response = reqwest::Client::new()
+        .post(format!("{}/v1/auth/register", config.service_http_addr()))
+        .json(&body)
+        .send().await
+        .unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+fn error_codes(json: &Value) -> Vec<(String, String)> {
+    json["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| (error["field"].as_str().unwrap().to_string(), error["code"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+fn error(field: &str, code: &str) -> (String, String) {
+    (field.to_string(), code.to_string())
+}
+
+// a new username and email of the domain
+fn new_user(domain: &str) -> (String, String) {
+    let id = Uuid::new_v4();
+    (format!("register-{}", id), format!("register-{}@{}", id, domain))
+}
+
+async fn delete_users(config: &Config, emails: &[&str]) {
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    sqlx::query("DELETE FROM users WHERE email = ANY($1)")
+        .bind(emails)
+        .execute(&pgpool).await
+        .unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn registration_open_test() {
+    let config = utils::start_api_with(
+        &[
+            ("REGISTRATION_POLICY", "open"),
+            ("REGISTRATION_AUTO_LOGIN", "true"),
+            ("CAPTCHA_KIND", "static"),
+            ("CAPTCHA_STATIC_TOKEN", "solved"),
+            ("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", "60"),
+            ("JWT_EXPIRE_REFRESH_TOKEN_SECONDS", "120"),
+        ]
+    ).await;
+    let (username, email) = new_user("example.com");
+
+    // the CAPTCHA is checked first
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": "short" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("captcha_response", "invalid")]);
+
+    let (status, json) = register(
+        &config,
+        json!({ "username": "", "email": "not-an-email", "password": "short", "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    let codes = error_codes(&json);
+    assert_eq!(codes[..3], [error("username", "required"), error("email", "invalid"), error("password", "too_short")]);
+
+    // a guest user logged in
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(json["user"]["username"], username);
+    assert_eq!(json["user"]["roles"], "guest");
+    assert!(json["user"].get("password").is_none());
+    let access_token = json["tokens"]["access_token"].as_str().unwrap();
+    let response = reqwest::Client::new()
+        .get(format!("{}/v1/users/me", config.service_http_addr()))
+        .bearer_auth(access_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("username", "taken")]);
+
+    // regardless of the case
+    let (status, json) = register(
+        &config,
+        json!({ "username": username.to_uppercase(), "email": "other@example.com", "password": PASSWORD, "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("username", "taken")]);
+
+    // a taken email looks like a new user without the tokens, no user is added
+    let (other_username, _) = new_user("example.com");
+    let (status, json) = register(
+        &config,
+        json!({ "username": &other_username, "email": email.to_uppercase(), "password": PASSWORD, "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(json["user"]["username"], other_username);
+    assert_eq!(json["user"]["email"], email.to_uppercase());
+    assert!(json.get("tokens").is_none());
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    let (users,): (i64,) = sqlx::query_as("SELECT count(*) FROM users WHERE lower(email) = lower($1)")
+        .bind(&email)
+        .fetch_one(&pgpool).await
+        .unwrap();
+    assert_eq!(users, 1);
+
+    // the concurrent registrations are told apart by the violated index
+    let insert = |username: String, email: String| {
+        sqlx::query(
+            "INSERT INTO users (username, email, password, roles, created_at, updated_at) VALUES ($1, $2, '', 'guest', now(), now())"
+        )
+            .bind(username)
+            .bind(email)
+            .execute(&pgpool)
+    };
+    let e = insert(username.to_uppercase(), format!("other-{}", email)).await.unwrap_err();
+    assert_eq!(user_repo::taken_field(&e), Some("username"));
+    let e = insert(format!("other-{}", username), email.to_uppercase()).await.unwrap_err();
+    assert_eq!(user_repo::taken_field(&e), Some("email"));
+    let (status, json) = register(
+        &config,
+        json!({ "username": &other_username, "email": "other@example.com", "password": "short", "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(!error_codes(&json).contains(&error("username", "taken")));
+
+    delete_users(&config, &[&email]).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn registration_invite_test() {
+    let config = utils::start_api_with(
+        &[
+            ("REGISTRATION_POLICY", "invite_only"),
+            ("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", "60"),
+            ("JWT_EXPIRE_REFRESH_TOKEN_SECONDS", "120"),
+        ]
+    ).await;
+    let (username, email) = new_user("example.com");
+    let (other_username, other_email) = new_user("example.com");
+
+    // only an admin issues the invites
+    let user = User {
+        id: Uuid::new_v4(),
+        username: username.clone(),
+        email: email.clone(),
+        password: String::new(),
+        active: true,
+        roles: "guest".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    };
+    let admin = User { roles: "admin".to_string(), ..user.clone() };
+    let invite = |user: &User| {
+        reqwest::Client::new()
+            .post(format!("{}/v1/auth/invites", config.service_http_addr()))
+            .bearer_auth(jwt_auth::generate_tokens(user.clone(), &config).access_token)
+            .json(&json!({ "email": &email }))
+            .send()
+    };
+    assert_eq!(invite(&user).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    let response = invite(&admin).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let json: Value = response.json().await.unwrap();
+    let invite_code = json["invite_code"].as_str().unwrap();
+
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("invite_code", "required")]);
+
+    // the invite is valid for its email only
+    let (status, json) = register(
+        &config,
+        json!({ "username": &other_username, "email": &other_email, "password": PASSWORD, "invite_code": invite_code })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("invite_code", "invalid")]);
+
+    // no tokens without the auto login
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "invite_code": invite_code })
+    ).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert!(json.get("tokens").is_none());
+
+    // single-use
+    let (status, json) = register(
+        &config,
+        json!({ "username": &other_username, "email": &email.to_uppercase(), "password": PASSWORD, "invite_code": invite_code })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(error_codes(&json).contains(&error("invite_code", "invalid")));
+
+    delete_users(&config, &[&email]).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn registration_domain_test() {
+    let config = utils::start_api_with(
+        &[
+            ("REGISTRATION_POLICY", "domain_allowlist"),
+            ("REGISTRATION_ALLOWED_DOMAINS", "example.com, Example.org"),
+        ]
+    ).await;
+    let (username, email) = new_user("example.net");
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("email", "domain_not_allowed")]);
+
+    let (username, email) = new_user("EXAMPLE.ORG");
+    let (status, _) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD })
+    ).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    delete_users(&config, &[&email]).await;
+}
+
+#[tokio::test]
+#[serial]
+async fn registration_disabled_test() {
+    let config = utils::start_api_with(&[]).await;
+    let (username, email) = new_user("example.com");
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD })
+    ).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(json["status"], 404);
+}
+
+// accepts one response, fails for another one like an unavailable provider
+struct ProviderCaptcha;
+
+impl Captcha for ProviderCaptcha {
+    fn verify<'a>(&'a self, response: &'a str, _ip: Option<IpAddr>) -> VerifyFuture<'a> {
+        Box::pin(async move {
+            match response {
+                "provider-solved" => Ok(true),
+                "provider-down" => Err(CaptchaError::Provider("unavailable".to_string())),
+                _ => Ok(false),
+            }
+        })
+    }
+}
+
+#[tokio::test]
+#[serial]
+async fn registration_plugged_captcha_test() {
+    let plugins = Plugins { captcha: Some(Arc::new(ProviderCaptcha)), ..Default::default() };
+    let config = utils::try_start_api_with_plugins(&[("REGISTRATION_POLICY", "open")], plugins).await.unwrap();
+    let (username, email) = new_user("example.com");
+
+    let (status, json) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "captcha_response": "solved" })
+    ).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(error_codes(&json), [error("captcha_response", "invalid")]);
+
+    let (status, _) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "captcha_response": "provider-down" })
+    ).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+    let (status, _) = register(
+        &config,
+        json!({ "username": &username, "email": &email, "password": PASSWORD, "captcha_response": "provider-solved" })
+    ).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    delete_users(&config, &[&email]).await;
+}
@@ -0,0 +1,37 @@
+use reqwest::StatusCode;
+use serial_test::serial;
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const API_V1: &str = "v1";
+
+#[tokio::test]
+#[serial]
+async fn request_id_test() {
+    // load the test configuration and start the api server
+    utils::start_api().await;
+
+    let url = utils::build_url(API_V1, "unknown", "route");
+
+    // the request id of the caller is echoed back
+    let response = reqwest::Client
+        ::new()
+        .get(url.as_str())
+        .header("X-Request-Id", "support-request-42")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert_eq!(response.headers()["x-request-id"], "support-request-42");
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["request_id"], "support-request-42");
+
+    // a request id is generated if missing
+    let response = reqwest::get(url.as_str()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let request_id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert!(Uuid::parse_str(&request_id).is_ok());
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["request_id"], request_id);
+}
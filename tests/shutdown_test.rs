@@ -0,0 +1,130 @@
+use axum::{ routing::get, Router };
+use axum_web::{ application::{ app, shutdown::Shutdown }, infrastructure::listener };
+use reqwest::StatusCode;
+use serial_test::serial;
+use std::{ sync::Arc, time::Duration };
+use tokio::{ net::TcpListener, sync::oneshot, time::timeout };
+
+pub mod common;
+use common::utils;
+
+async fn slow_handler() -> &'static str {
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    "done"
+}
+
+async fn stuck_handler() -> &'static str {
+    tokio::time::sleep(Duration::from_secs(60)).await;
+    "done"
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().pool_max_idle_per_host(0).build().unwrap()
+}
+
+#[tokio::test]
+async fn drain_in_flight_requests_test() {
+    let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/slow", tcp_listener.local_addr().unwrap());
+    let app = Router::new().route("/slow", get(slow_handler));
+
+    let (drain_tx, drain_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(
+        listener::serve(tcp_listener, app, async { drain_rx.await.unwrap() }, Duration::from_secs(5))
+    );
+
+    let in_flight = tokio::spawn(client().get(&url).send());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drain_tx.send(()).unwrap();
+
+    // the in-flight request completes
+    let response = in_flight.await.unwrap().unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.text().await.unwrap(), "done");
+
+    // the server stops once drained and the new connections are refused
+    timeout(Duration::from_secs(1), server).await.unwrap().unwrap();
+    assert!(client().get(&url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn drain_timeout_test() {
+    let tcp_listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/stuck", tcp_listener.local_addr().unwrap());
+    let app = Router::new().route("/stuck", get(stuck_handler));
+
+    let (drain_tx, drain_rx) = oneshot::channel::<()>();
+    let server = tokio::spawn(
+        listener::serve(tcp_listener, app, async { drain_rx.await.unwrap() }, Duration::from_millis(200))
+    );
+
+    let in_flight = tokio::spawn(client().get(&url).send());
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    drain_tx.send(()).unwrap();
+
+    // the connections still open after the drain timeout are closed
+    timeout(Duration::from_secs(2), server).await.unwrap().unwrap();
+    assert!(timeout(Duration::from_secs(2), in_flight).await.unwrap().unwrap().is_err());
+}
+
+#[tokio::test]
+#[serial]
+async fn shutdown_test() {
+    let config = Arc::new(utils::config_with(&[("SHUTDOWN_PRE_STOP_DELAY_SECONDS", "1")]));
+    let service_http_addr = config.service_http_addr();
+
+    let shutdown = Shutdown::new();
+    let (api_ready_tx, api_ready_rx) = oneshot::channel();
+    let server = tokio::spawn(app::start_server_with_shutdown(config, shutdown.clone(), api_ready_tx));
+    timeout(Duration::from_secs(5), api_ready_rx).await.unwrap().unwrap();
+
+    let ready_url = format!("{}/health/ready", service_http_addr);
+    let live_url = format!("{}/health/live", service_http_addr);
+    let response = client().get(&ready_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    shutdown.trigger();
+
+    // during the pre-stop delay the readiness fails while the requests are still served
+    let response = client().get(&ready_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["ready"], false);
+    assert_eq!(json["shutting_down"], true);
+    let response = client().get(&live_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // then the server drains, stops the background jobs and closes the pool
+    timeout(Duration::from_secs(5), server).await.unwrap().unwrap();
+    assert!(client().get(&live_url).send().await.is_err());
+}
+
+#[tokio::test]
+async fn shutdown_tasks_test() {
+    let shutdown = Shutdown::new();
+    let (job_tx, job_rx) = oneshot::channel::<()>();
+    let (task_tx, task_rx) = oneshot::channel::<()>();
+    shutdown.spawn_job("job", async move {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let _ = job_tx.send(());
+    });
+    shutdown.spawn_task("task", async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        let _ = task_tx.send(());
+    });
+
+    // the jobs are stopped, the tasks completed even after the trigger
+    shutdown.trigger();
+    timeout(Duration::from_secs(2), shutdown.stop_jobs(Duration::from_secs(5))).await.unwrap();
+    assert!(job_rx.await.is_err());
+    assert!(task_rx.await.is_ok());
+
+    // up to the timeout
+    let (task_tx, task_rx) = oneshot::channel::<()>();
+    shutdown.spawn_task("stuck task", async move {
+        tokio::time::sleep(Duration::from_secs(60)).await;
+        let _ = task_tx.send(());
+    });
+    timeout(Duration::from_secs(2), shutdown.stop_jobs(Duration::from_millis(100))).await.unwrap();
+    assert!(task_rx.await.is_err());
+}
@@ -0,0 +1,51 @@
+use axum::{ routing::get, Router };
+use axum_web::api::router;
+use opentelemetry::{ global, trace::TracerProvider as _ };
+use opentelemetry_sdk::{
+    propagation::TraceContextPropagator,
+    testing::trace::InMemorySpanExporter,
+    trace::TracerProvider,
+};
+use reqwest::StatusCode;
+use tracing_subscriber::{ layer::SubscriberExt, util::SubscriberInitExt };
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_SPAN_ID: &str = "00f067aa0ba902b7";
+
+#[tokio::test]
+async fn trace_context_propagation_test() {
+    let exporter = InMemorySpanExporter::default();
+    let provider = TracerProvider::builder().with_simple_exporter(exporter.clone()).build();
+    global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let _subscriber = tracing_subscriber
+        ::registry()
+        .with(tracing_subscriber::EnvFilter::new("axum_web=info"))
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("test")))
+        .set_default();
+
+    let app = Router::new()
+        .route("/traced", get(|| async { "traced" }))
+        .layer(axum::middleware::from_fn(router::logging_middleware));
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let response = reqwest::Client
+        ::new()
+        .get(format!("http://{}/traced", addr))
+        .header("traceparent", format!("00-{}-{}-01", TRACE_ID, PARENT_SPAN_ID))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let spans = exporter.get_finished_spans().unwrap();
+    let span = spans
+        .iter()
+        .find(|span| span.name == "axum")
+        .unwrap();
+    assert_eq!(span.span_context.trace_id().to_string(), TRACE_ID);
+    assert_eq!(span.parent_span_id.to_string(), PARENT_SPAN_ID);
+}
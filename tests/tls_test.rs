@@ -0,0 +1,186 @@
+use axum_web::application::config::Config;
+use rcgen::{
+    BasicConstraints,
+    Certificate,
+    CertificateParams,
+    DistinguishedName,
+    DnType,
+    ExtendedKeyUsagePurpose,
+    IsCa,
+    KeyPair,
+};
+use reqwest::{ header, redirect::Policy, tls::TlsInfo, StatusCode };
+use serial_test::serial;
+use std::{ path::{ Path, PathBuf }, sync::Arc, time::Duration };
+use tokio::sync::oneshot::error::RecvError;
+
+pub mod common;
+use common::utils;
+
+struct Ca {
+    cert: Certificate,
+    key: KeyPair,
+}
+
+impl Ca {
+    fn new() -> Self {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(Vec::<String>::new()).unwrap();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name = common_name("axum-web test CA");
+        Self { cert: params.self_signed(&key).unwrap(), key }
+    }
+
+    // returns the certificate and the private key in PEM
+    fn issue(&self, usage: ExtendedKeyUsagePurpose) -> (Certificate, String) {
+        let key = KeyPair::generate().unwrap();
+        let mut params = CertificateParams::new(vec!["127.0.0.1".to_string()]).unwrap();
+        params.distinguished_name = common_name("127.0.0.1");
+        params.extended_key_usages = vec![usage];
+        (params.signed_by(&key, &self.cert, &self.key).unwrap(), key.serialize_pem())
+    }
+}
+
+fn common_name(name: &str) -> DistinguishedName {
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, name);
+    distinguished_name
+}
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn write_server_cert(dir: &Path, ca: &Ca) -> Certificate {
+    let (cert, key) = ca.issue(ExtendedKeyUsagePurpose::ServerAuth);
+    std::fs::write(dir.join("server.key"), key).unwrap();
+    std::fs::write(dir.join("server.pem"), cert.pem()).unwrap();
+    cert
+}
+
+// fails if the server could not be started
+async fn start_tls_api(dir: &Path, vars: &[(&str, &str)]) -> Result<Arc<Config>, RecvError> {
+    let cert_path = dir.join("server.pem");
+    let key_path = dir.join("server.key");
+    let tls_vars = [("TLS_CERT_PATH", cert_path.to_str().unwrap()), ("TLS_KEY_PATH", key_path.to_str().unwrap())];
+    let vars: Vec<_> = tls_vars.into_iter().chain(vars.iter().copied()).collect();
+    utils::try_start_api_with(&vars).await
+}
+
+fn client(ca: &Ca) -> reqwest::ClientBuilder {
+    reqwest::Client
+        ::builder()
+        .add_root_certificate(reqwest::Certificate::from_pem(ca.cert.pem().as_bytes()).unwrap())
+        .tls_info(true)
+}
+
+fn peer_certificate(response: &reqwest::Response) -> Vec<u8> {
+    let tls_info = response.extensions().get::<TlsInfo>().unwrap();
+    tls_info.peer_certificate().unwrap().to_vec()
+}
+
+#[tokio::test]
+#[serial]
+async fn tls_test() {
+    let dir = temp_dir("tls_test");
+    let ca = Ca::new();
+    let server_cert = write_server_cert(&dir, &ca);
+    let config = start_tls_api(&dir, &[
+        ("TLS_RELOAD_INTERVAL_SECONDS", "1"),
+        ("TLS_REDIRECT_HTTP_PORT", "3480"),
+    ]).await.unwrap();
+    let url = format!("{}/health/live", config.service_http_addr());
+
+    let response = client(&ca).build().unwrap().get(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(peer_certificate(&response), server_cert.der().to_vec());
+
+    // plain HTTP is redirected
+    let response = reqwest::Client
+        ::builder()
+        .redirect(Policy::none())
+        .build()
+        .unwrap()
+        .post("http://127.0.0.1:3480/v1/heartbeat/1?check=1")
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+        response.headers()[header::LOCATION],
+        format!("{}/v1/heartbeat/1?check=1", config.service_http_addr())
+    );
+
+    // the renewed certificate is served without a restart
+    let renewed_cert = write_server_cert(&dir, &ca);
+    let mut peer = Vec::new();
+    for _ in 0..50 {
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let client = client(&ca).build().unwrap();
+        if let Ok(response) = client.get(&url).send().await {
+            peer = peer_certificate(&response);
+            if peer == renewed_cert.der().to_vec() {
+                break;
+            }
+        }
+    }
+    assert_eq!(peer, renewed_cert.der().to_vec());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn mutual_tls_test() {
+    let dir = temp_dir("mutual_tls_test");
+    let ca = Ca::new();
+    write_server_cert(&dir, &ca);
+    std::fs::write(dir.join("ca.pem"), ca.cert.pem()).unwrap();
+    let ca_path = dir.join("ca.pem");
+    let config = start_tls_api(&dir, &[("TLS_CLIENT_CA_PATH", ca_path.to_str().unwrap())]).await.unwrap();
+
+    let url = format!("{}/health/live", config.service_http_addr());
+
+    // a client without a certificate is rejected
+    let result = client(&ca).build().unwrap().get(&url).send().await;
+    assert!(result.is_err());
+
+    // a client with a certificate issued by the CA is accepted
+    let (client_cert, client_key) = ca.issue(ExtendedKeyUsagePurpose::ClientAuth);
+    let identity = reqwest::Identity
+        ::from_pkcs8_pem(client_cert.pem().as_bytes(), client_key.as_bytes())
+        .unwrap();
+    let response = client(&ca).identity(identity).build().unwrap().get(&url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn invalid_certificate_test() {
+    let dir = temp_dir("invalid_certificate_test");
+    std::fs::write(dir.join("server.pem"), "not a certificate").unwrap();
+    std::fs::write(dir.join("server.key"), "not a key").unwrap();
+
+    // the server is not started, the process is not exited
+    assert!(start_tls_api(&dir, &[]).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[tokio::test]
+#[serial]
+async fn redirect_port_taken_test() {
+    let dir = temp_dir("redirect_port_taken_test");
+    let ca = Ca::new();
+    write_server_cert(&dir, &ca);
+    let taken = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = taken.local_addr().unwrap().port().to_string();
+
+    // the server is not started, the process does not panic
+    assert!(start_tls_api(&dir, &[("TLS_REDIRECT_HTTP_PORT", port.as_str())]).await.is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
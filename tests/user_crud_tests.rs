@@ -1,6 +1,6 @@
 pub mod common;
 use axum_web::{
-    application::security::jwt_claims::{self, AccessClaims},
+    application::{config, security::jwt_claims::{self, AccessClaims}},
     domain::models::user::User,
 };
 use common::{auth, utils, *};
@@ -19,14 +19,14 @@ async fn list_users_test() {
     let (status, _) = users::list("xyz").await.unwrap();
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
     let (access_token, _) = result.unwrap();
 
-    let access_claims = jwt_claims::decode_token::<AccessClaims>(&access_token).unwrap();
-    let user_id = access_claims.sub.parse().unwrap();
+    let access_claims = jwt_claims::decode_token::<AccessClaims>(&access_token, &config::get()).unwrap();
+    let user_id: Uuid = access_claims.sub.parse().unwrap();
 
     // try authorized access to the users handler
     let (status, result) = users::list(&access_token).await.unwrap();
@@ -48,13 +48,13 @@ async fn get_user_test() {
     let (status, _) = users::get(uuid::Uuid::new_v4(), "").await.unwrap();
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
     let (access_token, _) = result.unwrap();
 
-    let access_claims = jwt_claims::decode_token::<AccessClaims>(&access_token).unwrap();
+    let access_claims = jwt_claims::decode_token::<AccessClaims>(&access_token, &config::get()).unwrap();
     let user_id = access_claims.sub.parse().unwrap();
 
     // get the user
@@ -77,12 +77,12 @@ async fn add_get_update_delete_user_test() {
         id: Uuid::new_v4(),
         username: username.clone(),
         email: format!("{}@email.com", username),
-        password_hash: "xyz123".to_string(),
-        password_salt: "xyz123".to_string(),
+        password: "Correct-Horse-Battery-42".to_string(),
         active: true,
         roles: "guest".to_string(),
         created_at: None,
         updated_at: None,
+        email_verified_at: None,
     };
 
     // try unauthorized access to user handlers
@@ -99,7 +99,7 @@ async fn add_get_update_delete_user_test() {
     let status = users::delete(user.id, &access_token).await.unwrap();
     assert_eq!(status, StatusCode::UNAUTHORIZED);
 
-    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD_HASH)
+    let (status, result) = auth::login(TEST_ADMIN_USERNAME, TEST_ADMIN_PASSWORD)
         .await
         .unwrap();
     assert_eq!(status, StatusCode::OK);
@@ -113,6 +113,9 @@ async fn add_get_update_delete_user_test() {
     assert!(user_result.updated_at.is_some());
     assert!(user_result.created_at.is_some());
 
+    // the password is stored hashed, the hash is sent back unchanged by the update
+    assert_ne!(user_result.password, user.password);
+    user.password = user_result.password.clone();
     user.created_at = user_result.created_at;
     user.updated_at = user_result.updated_at;
     assert_eq!(user_result, user);
@@ -0,0 +1,206 @@
+use axum_web::{
+    application::{ config::Config, security::{ jwt_auth, password } },
+    domain::models::user::User,
+};
+use reqwest::StatusCode;
+use serde_json::json;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub mod common;
+use common::utils;
+
+const PASSWORD: &str = "Correct-Horse-Battery-42";
+const NEW_PASSWORD: &str = "Purple-Monkey-Dishwasher-7";
+
+// the tokens outlive the slow password hashing of the debug builds
+async fn start_api() -> Arc<Config> {
+    utils::start_api_with(&[
+        ("JWT_EXPIRE_ACCESS_TOKEN_SECONDS", "60"),
+        ("JWT_EXPIRE_REFRESH_TOKEN_SECONDS", "120"),
+    ]).await
+}
+
+fn user(config: &Config) -> User {
+    let id = Uuid::new_v4();
+    User {
+        id,
+        username: format!("me-{}", id),
+        email: format!("me-{}@example.com", id),
+        password: password::hash_password(PASSWORD.as_bytes(), config),
+        active: true,
+        roles: "guest".to_string(),
+        created_at: None,
+        updated_at: None,
+        email_verified_at: None,
+    }
+}
+
+async fn insert_user(pgpool: &sqlx::PgPool, user: &User) {
+    sqlx::query("INSERT INTO users (id, username, email, password, active, roles, created_at, updated_at) VALUES ($1, $2, $3, $4, $5, $6, now(), now())")
+        .bind(user.id)
+        .bind(&user.username)
+        .bind(&user.email)
+        .bind(&user.password)
+        .bind(user.active)
+        .bind(&user.roles)
+        .execute(pgpool).await
+        .unwrap();
+}
+
+fn error_codes(json: &serde_json::Value) -> Vec<(String, String)> {
+    json["errors"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|error| (error["field"].as_str().unwrap().to_string(), error["code"].as_str().unwrap().to_string()))
+        .collect()
+}
+
+#[tokio::test]
+async fn users_me_test() {
+    let config = start_api().await;
+    let pgpool = sqlx::PgPool::connect(&config.postgres_url()).await.unwrap();
+    let user = user(&config);
+    let other = self::user(&config);
+    insert_user(&pgpool, &user).await;
+    insert_user(&pgpool, &other).await;
+
+    let client = reqwest::Client::new();
+    let me_url = format!("{}/v1/users/me", config.service_http_addr());
+    let access_token = jwt_auth::generate_tokens(user.clone(), &config).access_token;
+
+    // the own profile without the password hash, the admin routes stay closed
+    let response = client.get(&me_url).bearer_auth(&access_token).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["id"], user.id.to_string());
+    assert_eq!(json["username"], user.username);
+    assert!(json.get("password").is_none());
+    let response = client
+        .get(format!("{}/v1/users/{}", config.service_http_addr(), user.id))
+        .bearer_auth(&access_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = client.get(&me_url).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // the email change requires the current password
+    let new_email = format!("renamed-{}@example.com", user.id);
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &new_email }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error_codes(&json), [("current_password".to_string(), "required".to_string())]);
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &new_email, "current_password": "wrong" }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error_codes(&json), [("current_password".to_string(), "mismatch".to_string())]);
+
+    // the taken and the invalid values are reported by field
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "username": &other.username, "email": "not-an-email", "current_password": PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(
+        error_codes(&json),
+        [("username".to_string(), "taken".to_string()), ("email".to_string(), "invalid".to_string())]
+    );
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &other.email, "current_password": PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error_codes(&json), [("email".to_string(), "taken".to_string())]);
+
+    let response = client
+        .patch(&me_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "email": &new_email, "current_password": PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(json["email"], new_email);
+    assert_eq!(json["username"], user.username);
+
+    // the current password is required and the new one follows the policy
+    let password_url = format!("{}/password", me_url);
+    let response = client
+        .post(&password_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "current_password": "wrong", "new_password": NEW_PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error_codes(&json), [("current_password".to_string(), "mismatch".to_string())]);
+    let response = client
+        .post(&password_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "current_password": PASSWORD, "new_password": "short" }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let json: serde_json::Value = response.json().await.unwrap();
+    assert_eq!(error_codes(&json)[0], ("new_password".to_string(), "too_short".to_string()));
+
+    // the other sessions are revoked, the current device gets new tokens
+    let other_device = jwt_auth::generate_tokens(user.clone(), &config);
+    let response = client
+        .post(&password_url)
+        .bearer_auth(&access_token)
+        .json(&json!({ "current_password": PASSWORD, "new_password": NEW_PASSWORD }))
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let json: serde_json::Value = response.json().await.unwrap();
+    let new_access_token = json["access_token"].as_str().unwrap();
+    let new_refresh_token = json["refresh_token"].as_str().unwrap();
+
+    let response = client.get(&me_url).bearer_auth(&other_device.access_token).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let response = client
+        .post(format!("{}/v1/auth/refresh", config.service_http_addr()))
+        .bearer_auth(&other_device.refresh_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = client.get(&me_url).bearer_auth(new_access_token).send().await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = client
+        .post(format!("{}/v1/auth/refresh", config.service_http_addr()))
+        .bearer_auth(new_refresh_token)
+        .send().await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let stored: String = sqlx::query_scalar("SELECT password FROM users WHERE id = $1")
+        .bind(user.id)
+        .fetch_one(&pgpool).await
+        .unwrap();
+    assert!(password::verify_password(&stored, NEW_PASSWORD.as_bytes(), &config).is_ok());
+
+    sqlx::query("DELETE FROM users WHERE id = ANY($1)")
+        .bind(vec![user.id, other.id])
+        .execute(&pgpool).await
+        .unwrap();
+}